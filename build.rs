@@ -0,0 +1,24 @@
+use std::process::Command;
+
+/// Exposes the commit being built as `GIT_SHA` for the readiness check.
+/// Builds without a git checkout (e.g. Docker) can pass `GIT_SHA` in.
+fn main() {
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    let git_sha = std::env::var("GIT_SHA").ok().or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short=12", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|sha| sha.trim().to_string())
+    });
+
+    println!(
+        "cargo:rustc-env=GIT_SHA={}",
+        git_sha.unwrap_or_else(|| "unknown".to_string())
+    );
+}
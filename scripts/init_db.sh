@@ -44,5 +44,13 @@ surreal import --conn http://localhost:8000 -u surreal -p password --ns default
 surreal import --conn http://localhost:8000 -u surreal -p password --ns default --db newsletter schemas/20230613_101704_create_subscription_tokens_table.surql
 surreal import --conn http://localhost:8000 -u surreal -p password --ns default --db newsletter schemas/20230613_101705_create_users_table.surql
 surreal import --conn http://localhost:8000 -u surreal -p password --ns default --db newsletter schemas/20230613_101706_rename_password_column.surql
+surreal import --conn http://localhost:8000 -u surreal -p password --ns default --db newsletter schemas/20230620_101707_add_deactivated_at_to_users.surql
+surreal import --conn http://localhost:8000 -u surreal -p password --ns default --db newsletter schemas/20230622_101708_create_sessions_table.surql
+surreal import --conn http://localhost:8000 -u surreal -p password --ns default --db newsletter schemas/20230624_101709_create_newsletter_issues_table.surql
+surreal import --conn http://localhost:8000 -u surreal -p password --ns default --db newsletter schemas/20230626_101710_add_two_factor_to_users.surql
+surreal import --conn http://localhost:8000 -u surreal -p password --ns default --db newsletter schemas/20230628_101711_create_api_tokens_table.surql
+surreal import --conn http://localhost:8000 -u surreal -p password --ns default --db newsletter schemas/20230630_101712_add_role_to_users.surql
+surreal import --conn http://localhost:8000 -u surreal -p password --ns default --db newsletter schemas/20230702_101713_create_audit_log_table.surql
+surreal import --conn http://localhost:8000 -u surreal -p password --ns default --db newsletter schemas/20230704_101714_add_totp_last_step_to_users.surql
 
 >&2 echo "SurrealDB migrations applied! Let's Go!!!!"
\ No newline at end of file
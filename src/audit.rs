@@ -0,0 +1,223 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+use crate::authentication::{forwarded_client_ip, LoginThrottle};
+
+// region: -- Audit Context
+/// Who is on the other end of the request: client IP and user agent. The IP
+/// is the one the throttle keys on when a trusted `client_ip_header` is
+/// configured, and the socket address otherwise.
+#[derive(Clone, Debug)]
+pub struct AuditContext {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+impl AuditContext {
+    /// Context for changes made through the `zero2axum` CLI.
+    pub fn cli() -> Self {
+        Self {
+            ip: None,
+            user_agent: Some(format!("zero2axum-cli/{}", env!("CARGO_PKG_VERSION"))),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuditContext
+where
+    S: Send + Sync,
+    LoginThrottle: FromRef<S>,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ip = match LoginThrottle::from_ref(state).client_ip_header() {
+            Some(header) => forwarded_client_ip(&parts.headers, header),
+            None => parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip()),
+        };
+        let user_agent = parts
+            .headers
+            .get("User-Agent")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        Ok(Self { ip, user_agent })
+    }
+}
+// endregion: -- Audit Context
+
+// region: -- Audit Entry
+/// One append-only audit record. `action` is a dotted event name such as
+/// `login.failure` or `newsletter.publish`; `diff` holds `{field: {from, to}}`
+/// pairs or other event details.
+#[derive(Debug, Default)]
+pub struct AuditEntry {
+    pub action: &'static str,
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub diff: Option<serde_json::Value>,
+}
+
+impl AuditEntry {
+    pub fn new(action: &'static str, actor: impl Into<String>) -> Self {
+        Self {
+            action,
+            actor: Some(actor.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    pub fn diff(mut self, diff: serde_json::Value) -> Self {
+        self.diff = Some(diff);
+        self
+    }
+}
+
+/// `{"<field>": {"from": <from>, "to": <to>}}`
+pub fn change(field: &str, from: impl Serialize, to: impl Serialize) -> serde_json::Value {
+    serde_json::json!({ field: { "from": from, "to": to } })
+}
+
+/// Appends `entry` to the audit log. A failed write is logged but never fails
+/// the action being audited.
+#[tracing::instrument(
+    name = "Record audit entry",
+    skip(conn, context, entry),
+    fields(action = %entry.action)
+)]
+pub async fn record(conn: &Surreal<Client>, context: &AuditContext, entry: AuditEntry) {
+    let sql = "CREATE audit_log SET action = $action, actor = $actor, ip = $ip, \
+        user_agent = $user_agent, target = $target, diff = $diff, created_at = time::now()";
+
+    let result = conn
+        .query(sql)
+        .bind(("action", entry.action))
+        .bind(("actor", &entry.actor))
+        .bind(("ip", context.ip.map(|ip| ip.to_string())))
+        .bind(("user_agent", &context.user_agent))
+        .bind(("target", &entry.target))
+        .bind(("diff", &entry.diff))
+        .await
+        .and_then(|res| res.check());
+
+    if let Err(e) = result {
+        tracing::error!(
+            error.cause_chain = ?e,
+            audit.actor = ?entry.actor,
+            audit.target = ?entry.target,
+            "Failed to record audit entry"
+        );
+    }
+}
+// endregion: -- Audit Entry
+
+// region: -- Query Audit Log
+#[derive(Deserialize, Debug, Default)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AuditRecord {
+    pub action: String,
+    pub actor: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub target: Option<String>,
+    pub diff: Option<serde_json::Value>,
+    pub created_at: String,
+}
+
+/// Newest first. `action` matches exactly or, when it ends in `.*`, by prefix.
+#[tracing::instrument(name = "Query audit log", skip(conn))]
+pub async fn query(
+    conn: &Surreal<Client>,
+    filter: &AuditFilter,
+    page: u32,
+    per_page: u32,
+) -> Result<Vec<AuditRecord>, surrealdb::Error> {
+    let mut conditions = Vec::new();
+    if filter.actor.is_some() {
+        conditions.push("actor = $actor");
+    }
+    match filter.action.as_deref() {
+        Some(action) if action.ends_with(".*") => {
+            conditions.push("string::startsWith(action, $action_prefix)")
+        }
+        Some(_) => conditions.push("action = $action"),
+        None => {}
+    }
+    if filter.target.is_some() {
+        conditions.push("target = $target");
+    }
+    if filter.since.is_some() {
+        conditions.push("created_at >= <datetime> $since");
+    }
+    if filter.until.is_some() {
+        conditions.push("created_at < <datetime> $until");
+    }
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    let sql = format!(
+        "SELECT action, actor, ip, user_agent, target, diff, <string> created_at AS created_at \
+        FROM audit_log {where_clause} ORDER BY created_at DESC LIMIT $limit START $start"
+    );
+
+    let mut res = conn
+        .query(sql)
+        .bind(("actor", &filter.actor))
+        .bind(("action", &filter.action))
+        .bind((
+            "action_prefix",
+            filter.action.as_deref().map(|a| a.trim_end_matches('*')),
+        ))
+        .bind(("target", &filter.target))
+        .bind(("since", filter.since.map(|since| since.to_rfc3339())))
+        .bind(("until", filter.until.map(|until| until.to_rfc3339())))
+        .bind(("limit", per_page))
+        .bind(("start", page.saturating_sub(1).saturating_mul(per_page)))
+        .await?
+        .check()?;
+
+    let records: Vec<AuditRecord> = res.take(0)?;
+    Ok(records)
+}
+// endregion: -- Query Audit Log
+
+#[cfg(test)]
+mod tests {
+    use super::change;
+
+    #[test]
+    fn changes_are_recorded_as_from_to_pairs() {
+        assert_eq!(
+            change("role", "editor", "viewer"),
+            serde_json::json!({ "role": { "from": "editor", "to": "viewer" } })
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use color_eyre::eyre::{eyre, Context};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+use unicode_segmentation::UnicodeSegmentation;
+use uuid::Uuid;
+
+use crate::{
+    authentication::Role,
+    error::{ApiTokenError, AuthError},
+    prometheus::time_query,
+};
+
+const TOKEN_PREFIX: &str = "z2a_";
+
+// region: -- Scopes
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiTokenScope {
+    #[serde(rename = "publish")]
+    Publish,
+    #[serde(rename = "subscribers:read")]
+    SubscribersRead,
+    /// Changing the log filter; only honoured for owners.
+    #[serde(rename = "logging:write")]
+    LoggingWrite,
+}
+
+impl std::fmt::Display for ApiTokenScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiTokenScope::Publish => write!(f, "publish"),
+            ApiTokenScope::SubscribersRead => write!(f, "subscribers:read"),
+            ApiTokenScope::LoggingWrite => write!(f, "logging:write"),
+        }
+    }
+}
+// endregion: -- Scopes
+
+// region: -- Create Token
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiTokenSummary {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiTokenScope>,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+    pub last_used_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StoredSummary {
+    id: Thing,
+    name: String,
+    scopes: Vec<ApiTokenScope>,
+    created_at: String,
+    expires_at: Option<String>,
+    last_used_at: Option<String>,
+}
+
+impl From<StoredSummary> for ApiTokenSummary {
+    fn from(stored: StoredSummary) -> Self {
+        Self {
+            id: stored.id.id.to_string(),
+            name: stored.name,
+            scopes: stored.scopes,
+            created_at: stored.created_at,
+            expires_at: stored.expires_at,
+            last_used_at: stored.last_used_at,
+        }
+    }
+}
+
+/// Creates a token for `user_id`. The returned secret is the only copy of the
+/// token - only its SHA-256 hash is stored.
+#[tracing::instrument(name = "Create API token", skip(user_id, conn))]
+pub async fn create_api_token(
+    user_id: &Thing,
+    name: &str,
+    scopes: &[ApiTokenScope],
+    expires_in_days: Option<u32>,
+    conn: &Surreal<Client>,
+) -> Result<(ApiTokenSummary, Secret<String>), ApiTokenError> {
+    validate_token_name(name)?;
+    if scopes.is_empty() {
+        return Err(ApiTokenError::ValidationError(
+            "A token needs at least one scope.".into(),
+        ));
+    }
+
+    let token = generate_api_token();
+    let id = Uuid::new_v4().simple().to_string();
+    let expires_at = match expires_in_days {
+        Some(0) => {
+            return Err(ApiTokenError::ValidationError(
+                "expires_in_days must be at least 1.".into(),
+            ))
+        }
+        Some(_) => "time::now() + <duration> $expires_in",
+        None => "NONE",
+    };
+    let sql = format!(
+        "CREATE type::thing('api_tokens', $id) SET user = $user_id, name = $name, \
+        token_hash = $token_hash, scopes = $scopes, created_at = time::now(), \
+        expires_at = {expires_at}"
+    );
+
+    conn.query(sql)
+        .bind(("id", &id))
+        .bind(("user_id", user_id))
+        .bind(("name", name))
+        .bind(("token_hash", hash_api_token(&token)))
+        .bind(("scopes", scopes))
+        .bind(("expires_in", expires_in_days.map(|days| format!("{days}d"))))
+        .await?
+        .check()?;
+
+    let summary = list_api_tokens(user_id, conn)
+        .await?
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| eyre!("The new token was not returned by SurrealDB."))?;
+
+    Ok((summary, token))
+}
+// endregion: -- Create Token
+
+// region: -- List & Revoke Tokens
+const SUMMARY_FIELDS: &str = "id, name, scopes, \
+    <string> created_at AS created_at, \
+    IF expires_at != NONE THEN <string> expires_at END AS expires_at, \
+    IF last_used_at != NONE THEN <string> last_used_at END AS last_used_at";
+
+#[tracing::instrument(name = "List API tokens", skip(user_id, conn))]
+pub async fn list_api_tokens(
+    user_id: &Thing,
+    conn: &Surreal<Client>,
+) -> Result<Vec<ApiTokenSummary>, ApiTokenError> {
+    let sql = format!(
+        "SELECT {SUMMARY_FIELDS} FROM api_tokens WHERE user = $user_id ORDER BY created_at"
+    );
+
+    let mut res = conn.query(sql).bind(("user_id", user_id)).await?.check()?;
+    let tokens: Vec<StoredSummary> = res.take(0)?;
+    Ok(tokens.into_iter().map(ApiTokenSummary::from).collect())
+}
+
+/// Deletes one of `user_id`'s tokens. Tokens of other users are reported as
+/// unknown rather than forbidden.
+#[tracing::instrument(name = "Revoke API token", skip(user_id, conn))]
+pub async fn revoke_api_token(
+    user_id: &Thing,
+    token_id: &str,
+    conn: &Surreal<Client>,
+) -> Result<(), ApiTokenError> {
+    let sql = "DELETE type::thing('api_tokens', $token_id) WHERE user = $user_id RETURN BEFORE";
+
+    let mut res = conn
+        .query(sql)
+        .bind(("token_id", token_id))
+        .bind(("user_id", user_id))
+        .await?
+        .check()?;
+
+    let deleted: Option<Thing> = res.take((0, "id"))?;
+    deleted.map(|_| ()).ok_or(ApiTokenError::UnknownToken)
+}
+// endregion: -- List & Revoke Tokens
+
+// region: -- Authenticate Token
+#[derive(Debug)]
+pub struct ApiTokenOwner {
+    pub user_id: Thing,
+    pub username: String,
+    pub role: Role,
+}
+
+#[derive(Deserialize)]
+struct StoredToken {
+    id: Thing,
+    user: Thing,
+    username: String,
+    role: Role,
+    scopes: Vec<ApiTokenScope>,
+}
+
+/// Resolves a bearer token to its (active) owner, provided it has not expired
+/// and carries `required_scope`. Records the time of use.
+#[tracing::instrument(name = "Authenticate API token", skip(token, conn))]
+pub async fn authenticate_api_token(
+    token: &Secret<String>,
+    required_scope: ApiTokenScope,
+    conn: &Surreal<Client>,
+) -> Result<ApiTokenOwner, AuthError> {
+    let sql = "SELECT id, user, user.username AS username, user.role AS role, scopes \
+        FROM api_tokens \
+        WHERE token_hash = $token_hash \
+        AND (expires_at = NONE OR expires_at > time::now()) \
+        AND user.deactivated_at = NONE";
+
+    let mut res = time_query(
+        "api_token.authenticate",
+        conn.query(sql).bind(("token_hash", hash_api_token(token))),
+    )
+    .await
+    .context("Failed to look up the API token")?
+    .check()
+    .context("Failed to look up the API token")?;
+    let stored: Option<StoredToken> = res.take(0).context("Failed to look up the API token")?;
+
+    let stored = stored
+        .ok_or_else(|| eyre!("Unknown or expired API token."))
+        .map_err(AuthError::InvalidCredentials)?;
+    if !stored.scopes.contains(&required_scope) {
+        return Err(AuthError::InvalidCredentials(eyre!(
+            "The API token lacks the {} scope.",
+            required_scope
+        )));
+    }
+
+    conn.query("UPDATE $token SET last_used_at = time::now()")
+        .bind(("token", &stored.id))
+        .await
+        .context("Failed to record API token use")?
+        .check()
+        .context("Failed to record API token use")?;
+
+    Ok(ApiTokenOwner {
+        user_id: stored.user,
+        username: stored.username,
+        role: stored.role,
+    })
+}
+// endregion: -- Authenticate Token
+
+// region: -- Helpers
+fn generate_api_token() -> Secret<String> {
+    let token: String = thread_rng()
+        .sample_iter(Alphanumeric)
+        .map(char::from)
+        .take(40)
+        .collect();
+    Secret::new(format!("{TOKEN_PREFIX}{token}"))
+}
+
+fn hash_api_token(token: &Secret<String>) -> String {
+    format!("{:x}", Sha256::digest(token.expose_secret().as_bytes()))
+}
+
+fn validate_token_name(name: &str) -> Result<(), ApiTokenError> {
+    if name.trim().is_empty() || name.graphemes(true).count() > 64 {
+        Err(ApiTokenError::ValidationError(
+            "A token name must be between 1 and 64 characters.".into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+// endregion: -- Helpers
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_api_token, validate_token_name, ApiTokenScope, TOKEN_PREFIX};
+    use claims::{assert_err, assert_ok};
+    use secrecy::ExposeSecret;
+
+    #[test]
+    fn tokens_are_prefixed_and_unique() {
+        let a = generate_api_token();
+        let b = generate_api_token();
+        assert!(a.expose_secret().starts_with(TOKEN_PREFIX));
+        assert_ne!(a.expose_secret(), b.expose_secret());
+    }
+
+    #[test]
+    fn scopes_use_their_wire_names() {
+        let scopes: Vec<ApiTokenScope> =
+            serde_json::from_str(r#"["publish", "subscribers:read"]"#).unwrap();
+        assert_eq!(
+            scopes,
+            vec![ApiTokenScope::Publish, ApiTokenScope::SubscribersRead]
+        );
+    }
+
+    #[test]
+    fn token_names_must_not_be_blank() {
+        assert_ok!(validate_token_name("ci"));
+        assert_err!(validate_token_name(" "));
+        assert_err!(validate_token_name(&"a".repeat(65)));
+    }
+}
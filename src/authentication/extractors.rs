@@ -0,0 +1,93 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::request::Parts,
+    response::{IntoResponse, Redirect, Response},
+};
+use base64::Engine;
+use color_eyre::eyre::Context;
+use hyper::HeaderMap;
+use secrecy::Secret;
+use surrealdb::sql::Thing;
+
+use crate::{authentication::Credentials, session::Session, startup::AppState};
+
+// region: -- Admin User Extractor
+/// A logged-in user. Anonymous requests are redirected to the login form.
+#[derive(Debug)]
+pub struct AdminUser {
+    pub user_id: Thing,
+    pub username: String,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let session = Session::from_request_parts(parts, state).await?;
+
+        match (session.user_id(), session.username()) {
+            (Some(user_id), Some(username)) => Ok(Self {
+                user_id: user_id.clone(),
+                username: username.to_string(),
+            }),
+            _ => Err(Redirect::to("/login").into_response()),
+        }
+    }
+}
+// endregion: -- Admin User Extractor
+
+// region: -- Basic Authentication
+#[tracing::instrument(name = "Basic Authentication", skip(headers))]
+pub fn basic_authentication(headers: &HeaderMap) -> color_eyre::Result<Credentials> {
+    let header_value = headers
+        .get("Authorization")
+        .ok_or_else(|| color_eyre::eyre::eyre!("Missing authorization header"))?
+        .to_str()
+        .context("Authorization header was not valid UTF8")?;
+
+    let base64encoded_credentials = header_value
+        .strip_prefix("Basic ")
+        .ok_or_else(|| color_eyre::eyre::eyre!("Authorization header did not start with Basic"))?;
+
+    let decoded_credentials = base64::engine::general_purpose::STANDARD
+        .decode(base64encoded_credentials)
+        .map_err(|e| color_eyre::eyre::eyre!("Failed to base64-decode credentials: {}", e))?;
+
+    let decoded_credentials = String::from_utf8(decoded_credentials)
+        .map_err(|e| color_eyre::eyre::eyre!("Credentials were not valid UTF8: {}", e))?;
+
+    let mut credentials = decoded_credentials.splitn(2, ':');
+
+    let username = credentials
+        .next()
+        .ok_or_else(|| color_eyre::eyre::eyre!("A username must be provided in 'Basic' auth."))?
+        .to_owned();
+
+    let password = credentials
+        .next()
+        .ok_or_else(|| color_eyre::eyre::eyre!("A password must be provided in 'Basic' auth."))?
+        .to_owned();
+
+    Ok(Credentials {
+        username,
+        password: Secret::new(password),
+    })
+}
+// endregion: -- Basic Authentication
+
+// region: -- Bearer Authentication
+/// The token of an `Authorization: Bearer <token>` header, if there is one.
+pub fn bearer_token(headers: &HeaderMap) -> Option<Secret<String>> {
+    headers
+        .get("Authorization")?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(|token| Secret::new(token.trim().to_string()))
+}
+// endregion: -- Bearer Authentication
@@ -0,0 +1,15 @@
+mod api_tokens;
+mod extractors;
+mod password;
+mod roles;
+mod throttle;
+mod two_factor;
+mod users;
+
+pub use api_tokens::*;
+pub use extractors::*;
+pub use password::*;
+pub use roles::*;
+pub use throttle::*;
+pub use two_factor::*;
+pub use users::*;
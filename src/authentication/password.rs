@@ -0,0 +1,276 @@
+use argon2::{
+    password_hash::SaltString, Algorithm, Argon2, Params, PasswordHash, PasswordHasher,
+    PasswordVerifier, Version,
+};
+use std::{collections::HashMap, sync::Mutex};
+
+use color_eyre::eyre::Context;
+use once_cell::sync::Lazy;
+use secrecy::{ExposeSecret, Secret};
+use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+use uuid::Uuid;
+
+use crate::{
+    configuration::Argon2Settings,
+    error::{AuthError, PublishError},
+    prometheus::time_query,
+    telemetry::spawn_block_with_tracing,
+};
+
+#[derive(serde::Deserialize)]
+pub struct Credentials {
+    pub username: String,
+    pub password: Secret<String>,
+}
+
+// region: -- Validate Credentials
+#[tracing::instrument(name = "Validating credentials", skip(credentials, argon2, conn))]
+pub async fn validate_credentials(
+    credentials: Credentials,
+    argon2: &Argon2Settings,
+    conn: &Surreal<Client>,
+) -> Result<Thing, AuthError> {
+    let (user_id, expected_password_hash) =
+        match get_stored_credentials(&credentials.username, conn).await? {
+            Some((user_id, password_hash)) => (Some(user_id), Some(password_hash)),
+            None => (None, None),
+        };
+
+    let stored_password_hash = expected_password_hash.clone();
+    let password = credentials.password.clone();
+    let settings = argon2.clone();
+
+    spawn_block_with_tracing(move || {
+        let expected_password_hash = match expected_password_hash {
+            Some(password_hash) => password_hash,
+            None => dummy_password_hash(&settings)?,
+        };
+        verify_password_hash(expected_password_hash, credentials.password)
+    })
+    .await
+    .context("Failed to spawn blocking task")??;
+
+    let user_id = user_id
+        .ok_or_else(|| color_eyre::eyre::eyre!("Unknown username."))
+        .map_err(AuthError::InvalidCredentials)?;
+
+    if stored_password_hash.map_or(false, |password_hash| needs_rehash(&password_hash, argon2)) {
+        // The password was correct - a failed upgrade must not fail the login
+        if let Err(e) = upgrade_password_hash(&user_id, password, argon2, conn).await {
+            tracing::warn!(error.cause_chain = ?e, "Failed to upgrade password hash");
+        }
+    }
+
+    Ok(user_id)
+}
+
+/// What unknown usernames are verified against, hashed with the configured
+/// costs so rejecting them takes as long as checking a real password. Computed
+/// once per set of Argon2 parameters; call it at startup to keep the first
+/// computation off the login path.
+pub fn dummy_password_hash(argon2: &Argon2Settings) -> color_eyre::Result<Secret<String>> {
+    /// Memory size, iterations and parallelism.
+    type Costs = (u32, u32, u32);
+    static DUMMY_PASSWORD_HASHES: Lazy<Mutex<HashMap<Costs, Secret<String>>>> =
+        Lazy::new(Default::default);
+
+    let key = (
+        argon2.memory_size_kib,
+        argon2.iterations,
+        argon2.parallelism,
+    );
+    let mut hashes = DUMMY_PASSWORD_HASHES.lock().unwrap();
+    if let Some(password_hash) = hashes.get(&key) {
+        return Ok(password_hash.clone());
+    }
+
+    let password_hash = compute_password_hash(Secret::new(Uuid::new_v4().to_string()), argon2)?;
+    hashes.insert(key, password_hash.clone());
+    Ok(password_hash)
+}
+// endregion: -- Validate Credentials
+
+// region: -- Password Hash Upgrades
+/// Whether a stored hash is weaker than `argon2`. Hashes are only ever
+/// strengthened: lowering the configured costs leaves existing hashes alone.
+pub fn needs_rehash(password_hash: &Secret<String>, argon2: &Argon2Settings) -> bool {
+    let Ok(password_hash) = PasswordHash::new(password_hash.expose_secret()) else {
+        return true;
+    };
+    let Ok(params) = Params::try_from(&password_hash) else {
+        return true;
+    };
+
+    password_hash.algorithm != Algorithm::Argon2id.ident()
+        || password_hash.version != Some(Version::V0x13.into())
+        || params.m_cost() < argon2.memory_size_kib
+        || params.t_cost() < argon2.iterations
+        || params.p_cost() < argon2.parallelism
+}
+
+#[tracing::instrument(name = "Upgrade password hash", skip(password, argon2, conn))]
+async fn upgrade_password_hash(
+    user_id: &Thing,
+    password: Secret<String>,
+    argon2: &Argon2Settings,
+    conn: &Surreal<Client>,
+) -> color_eyre::Result<()> {
+    let settings = argon2.clone();
+    let password_hash =
+        spawn_block_with_tracing(move || compute_password_hash(password, &settings))
+            .await
+            .context("Failed to spawn blocking task")??;
+
+    let sql = "UPDATE users SET password_hash = $password_hash WHERE id = $user_id";
+    conn.query(sql)
+        .bind(("password_hash", password_hash.expose_secret()))
+        .bind(("user_id", user_id))
+        .await
+        .context("Failed to store the upgraded password hash")?
+        .check()?;
+
+    Ok(())
+}
+// endregion: -- Password Hash Upgrades
+
+// region: -- Verify Password Hash
+#[tracing::instrument(
+    name = "Verify password hash",
+    skip(expected_password_hash, password_candidate)
+)]
+fn verify_password_hash(
+    expected_password_hash: Secret<String>,
+    password_candidate: Secret<String>,
+) -> Result<(), AuthError> {
+    let expected_password_hash = PasswordHash::new(expected_password_hash.expose_secret())
+        .context("Failed to parse hash in PHC string format.")?;
+
+    Argon2::default()
+        .verify_password(
+            password_candidate.expose_secret().as_bytes(),
+            &expected_password_hash,
+        )
+        .context("Invalid password.")
+        .map_err(AuthError::InvalidCredentials)
+}
+// endregion: -- Verify Password Hash
+
+// region: -- Compute Password Hash
+pub fn compute_password_hash(
+    password: Secret<String>,
+    argon2: &Argon2Settings,
+) -> color_eyre::Result<Secret<String>> {
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let password_hash = argon2
+        .hasher()
+        .map_err(|e| color_eyre::eyre::eyre!("Invalid Argon2 parameters: {}", e))?
+        .hash_password(password.expose_secret().as_bytes(), &salt)?
+        .to_string();
+
+    Ok(Secret::new(password_hash))
+}
+// endregion: -- Compute Password Hash
+
+// region: -- Get Stored Credentials
+#[derive(Debug, serde::Deserialize)]
+struct StoredCredentials {
+    id: Thing,
+    password_hash: String,
+}
+
+#[tracing::instrument(name = "Get stored credentials", skip(username, conn))]
+async fn get_stored_credentials(
+    username: &str,
+    conn: &Surreal<Client>,
+) -> color_eyre::Result<Option<(Thing, Secret<String>)>> {
+    let sql = "SELECT id, password_hash FROM users \
+        WHERE username = $username AND deactivated_at = NONE";
+
+    let mut res = time_query(
+        "user.credentials",
+        conn.query(sql).bind(("username", username)),
+    )
+    .await
+    .context("Failed to perform a query to retrieve stored credentials")?
+    .check()
+    .map_err(|e| PublishError::UnexpectedError(color_eyre::eyre::eyre!(e)))?;
+
+    let creds: Option<StoredCredentials> = res
+        .take(0)
+        .map_err(|e| PublishError::UnexpectedError(color_eyre::eyre::eyre!(e)))?;
+
+    Ok(creds.map(|c| (c.id, Secret::new(c.password_hash))))
+}
+// endregion: -- Get Stored Credentials
+
+#[cfg(test)]
+mod tests {
+    use super::{compute_password_hash, dummy_password_hash, needs_rehash};
+    use crate::configuration::Argon2Settings;
+    use secrecy::{ExposeSecret, Secret};
+
+    fn hash_with(argon2: &Argon2Settings) -> Secret<String> {
+        compute_password_hash(Secret::new("password".to_string()), argon2).unwrap()
+    }
+
+    #[test]
+    fn the_dummy_hash_uses_the_configured_costs() {
+        let strong = Argon2Settings {
+            memory_size_kib: Argon2Settings::default().memory_size_kib * 2,
+            ..Argon2Settings::default()
+        };
+        let dummy = dummy_password_hash(&strong).unwrap();
+
+        assert!(!needs_rehash(&dummy, &strong));
+        assert_eq!(
+            dummy.expose_secret(),
+            dummy_password_hash(&strong).unwrap().expose_secret()
+        );
+    }
+
+    #[test]
+    fn a_hash_with_the_target_parameters_is_kept() {
+        let argon2 = Argon2Settings::default();
+        assert!(!needs_rehash(&hash_with(&argon2), &argon2));
+    }
+
+    #[test]
+    fn a_hash_with_weaker_parameters_is_upgraded() {
+        let weak = Argon2Settings {
+            memory_size_kib: 4096,
+            iterations: 1,
+            parallelism: 1,
+        };
+        assert!(needs_rehash(&hash_with(&weak), &Argon2Settings::default()));
+    }
+
+    #[test]
+    fn a_hash_with_stronger_parameters_is_not_downgraded() {
+        let strong = Argon2Settings {
+            memory_size_kib: Argon2Settings::default().memory_size_kib * 2,
+            iterations: Argon2Settings::default().iterations + 1,
+            ..Argon2Settings::default()
+        };
+        assert!(!needs_rehash(
+            &hash_with(&strong),
+            &Argon2Settings::default()
+        ));
+    }
+
+    #[test]
+    fn a_non_argon2id_hash_is_upgraded() {
+        let argon2i = Secret::new(
+            "$argon2i$v=19$m=15000,t=2,p=1$\
+            gZiV/M1gPc22ElAH/Jh1Hw$\
+            CWOrkoo7oJBQ/iyh7uJ0LO2aLEfrHwTWllSAxT0zRno"
+                .to_string(),
+        );
+        assert!(needs_rehash(&argon2i, &Argon2Settings::default()));
+    }
+
+    #[test]
+    fn an_unparseable_hash_is_upgraded() {
+        let garbage = Secret::new("not-a-phc-string".to_string());
+        assert!(needs_rehash(&garbage, &Argon2Settings::default()));
+    }
+}
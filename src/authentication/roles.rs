@@ -0,0 +1,168 @@
+use std::marker::PhantomData;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use color_eyre::eyre::{eyre, Context};
+use serde::{Deserialize, Serialize};
+use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+
+use crate::{authentication::AdminUser, error::AuthorizationError, startup::AppState};
+
+// region: -- Roles
+/// Ordered from least to most privileged, so `role >= Role::Editor` reads as
+/// "at least an editor".
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,
+    Editor,
+    Owner,
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::Viewer => write!(f, "viewer"),
+            Role::Editor => write!(f, "editor"),
+            Role::Owner => write!(f, "owner"),
+        }
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Role::Viewer),
+            "editor" => Ok(Role::Editor),
+            "owner" => Ok(Role::Owner),
+            other => Err(format!(
+                "{} is not a valid role. Use owner, editor or viewer.",
+                other
+            )),
+        }
+    }
+}
+
+#[tracing::instrument(name = "Get user role", skip(conn))]
+pub async fn get_user_role(user_id: &Thing, conn: &Surreal<Client>) -> color_eyre::Result<Role> {
+    let mut res = conn
+        .query("SELECT role FROM $user_id")
+        .bind(("user_id", user_id))
+        .await
+        .context("Failed to retrieve the user's role")?
+        .check()?;
+
+    let role: Option<Role> = res.take((0, "role"))?;
+    role.ok_or_else(|| eyre!("The user has no role."))
+}
+
+/// Fails with `AuthorizationError::Forbidden` unless `actual` is at least `required`.
+pub fn require_role(actual: Role, required: Role) -> Result<(), AuthorizationError> {
+    if actual >= required {
+        Ok(())
+    } else {
+        Err(AuthorizationError::Forbidden { required, actual })
+    }
+}
+// endregion: -- Roles
+
+// region: -- Authorisation Extractor
+/// What a handler needs to be allowed to do. Declared as the type parameter
+/// of `Authorized`.
+pub trait Permission: Send + Sync {
+    const MINIMUM_ROLE: Role;
+}
+
+/// Read-only access to the admin pages.
+#[derive(Debug)]
+pub struct CanView;
+/// Composing and publishing newsletter issues.
+#[derive(Debug)]
+pub struct CanPublish;
+/// Creating, re-passwording, re-roling and deactivating users.
+#[derive(Debug)]
+pub struct CanManageUsers;
+
+impl Permission for CanView {
+    const MINIMUM_ROLE: Role = Role::Viewer;
+}
+
+impl Permission for CanPublish {
+    const MINIMUM_ROLE: Role = Role::Editor;
+}
+
+impl Permission for CanManageUsers {
+    const MINIMUM_ROLE: Role = Role::Owner;
+}
+
+/// A logged-in user whose role satisfies `P`. Anonymous requests are
+/// redirected to the login form, insufficient roles get a 403.
+///
+/// The role is read from the database on every request, so a demotion takes
+/// effect without waiting for the session to expire.
+#[derive(Debug)]
+pub struct Authorized<P: Permission> {
+    pub user: AdminUser,
+    pub role: Role,
+    _permission: PhantomData<P>,
+}
+
+#[async_trait]
+impl<P: Permission> FromRequestParts<AppState> for Authorized<P> {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let user = AdminUser::from_request_parts(parts, state).await?;
+        let role = get_user_role(&user.user_id, &state.database.client)
+            .await
+            .map_err(|e| AuthorizationError::UnexpectedError(e).into_response())?;
+
+        require_role(role, P::MINIMUM_ROLE).map_err(|e| {
+            tracing::warn!(username = %user.username, error = %e, "Forbidden");
+            e.into_response()
+        })?;
+
+        Ok(Self {
+            user,
+            role,
+            _permission: PhantomData,
+        })
+    }
+}
+// endregion: -- Authorisation Extractor
+
+#[cfg(test)]
+mod tests {
+    use super::{require_role, Role};
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn higher_roles_include_lower_ones() {
+        assert_ok!(require_role(Role::Owner, Role::Editor));
+        assert_ok!(require_role(Role::Editor, Role::Editor));
+        assert_ok!(require_role(Role::Editor, Role::Viewer));
+    }
+
+    #[test]
+    fn lower_roles_are_forbidden() {
+        assert_err!(require_role(Role::Viewer, Role::Editor));
+        assert_err!(require_role(Role::Editor, Role::Owner));
+    }
+
+    #[test]
+    fn roles_round_trip_through_strings() {
+        for role in [Role::Viewer, Role::Editor, Role::Owner] {
+            assert_eq!(role.to_string().parse::<Role>().unwrap(), role);
+        }
+        assert_err!("admin".parse::<Role>());
+    }
+}
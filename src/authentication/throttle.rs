@@ -0,0 +1,392 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{request::Parts, HeaderMap},
+};
+use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+
+use crate::{
+    audit::{self, AuditContext, AuditEntry},
+    authentication::{validate_credentials, Credentials},
+    configuration::{Argon2Settings, LoginThrottleSettings},
+    error::AuthError,
+};
+
+// region: -- Login Throttle
+/// Counts failed logins per username and per client IP and locks a key out
+/// with an exponentially growing delay once its threshold is crossed.
+///
+/// Unknown usernames are tracked exactly like known ones, so a lockout says
+/// nothing about whether an account exists. Attempts made while a key is
+/// locked are not counted, so they cannot extend its lockout.
+#[derive(Clone)]
+pub struct LoginThrottle {
+    settings: LoginThrottleSettings,
+    attempts: Arc<Mutex<HashMap<ThrottleKey, FailedAttempts>>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum ThrottleKey {
+    Username(String),
+    Ip(IpAddr),
+}
+
+/// A lockout started by a failed attempt.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Lockout {
+    /// The username, or the client IP for per-IP lockouts.
+    pub key: String,
+    pub failures: u32,
+    pub duration: Duration,
+}
+
+#[derive(Debug)]
+struct FailedAttempts {
+    failures: u32,
+    last_failure: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl LoginThrottle {
+    pub fn new(settings: LoginThrottleSettings) -> Self {
+        Self {
+            settings,
+            attempts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the remaining lockout if either the username or the IP is locked.
+    pub fn check(&self, username: &str, ip: Option<IpAddr>) -> Option<Duration> {
+        self.check_at(username, ip, Instant::now())
+    }
+
+    /// Records a failed attempt for both keys, returning any lockouts it started.
+    pub fn record_failure(&self, username: &str, ip: Option<IpAddr>) -> Vec<Lockout> {
+        self.record_failure_at(username, ip, Instant::now())
+    }
+
+    /// The header a trusted proxy puts the client IP in, if one is configured.
+    pub fn client_ip_header(&self) -> Option<&str> {
+        self.settings.client_ip_header.as_deref()
+    }
+
+    /// A successful login clears the username's history. The IP keeps its
+    /// count, otherwise an attacker could reset it with their own account.
+    pub fn record_success(&self, username: &str) {
+        self.attempts
+            .lock()
+            .unwrap()
+            .remove(&ThrottleKey::Username(normalise(username)));
+    }
+
+    fn keys(&self, username: &str, ip: Option<IpAddr>) -> Vec<(ThrottleKey, u32)> {
+        let mut keys = vec![(
+            ThrottleKey::Username(normalise(username)),
+            self.settings.max_failures_per_username,
+        )];
+        if let Some(ip) = ip {
+            keys.push((ThrottleKey::Ip(ip), self.settings.max_failures_per_ip));
+        }
+        keys
+    }
+
+    fn check_at(&self, username: &str, ip: Option<IpAddr>, now: Instant) -> Option<Duration> {
+        if !self.settings.enabled {
+            return None;
+        }
+
+        let attempts = self.attempts.lock().unwrap();
+        self.keys(username, ip)
+            .iter()
+            .filter_map(|(key, _)| attempts.get(key)?.locked_until)
+            .filter(|locked_until| *locked_until > now)
+            .map(|locked_until| locked_until - now)
+            .max()
+    }
+
+    fn record_failure_at(&self, username: &str, ip: Option<IpAddr>, now: Instant) -> Vec<Lockout> {
+        if !self.settings.enabled {
+            return Vec::new();
+        }
+
+        let window = Duration::from_secs(self.settings.window_seconds);
+        let mut attempts = self.attempts.lock().unwrap();
+        attempts.retain(|_, a| {
+            now.duration_since(a.last_failure) < window
+                || a.locked_until.map_or(false, |until| until > now)
+        });
+
+        let mut lockouts = Vec::new();
+        for (key, max_failures) in self.keys(username, ip) {
+            let entry = attempts.entry(key.clone()).or_insert(FailedAttempts {
+                failures: 0,
+                last_failure: now,
+                locked_until: None,
+            });
+            if entry.locked_until.map_or(false, |until| until > now) {
+                continue;
+            }
+            entry.failures += 1;
+            entry.last_failure = now;
+
+            if entry.failures >= max_failures {
+                let duration = self.lockout_for(entry.failures - max_failures);
+                entry.locked_until = Some(now + duration);
+                lockouts.push(Lockout {
+                    key: match key {
+                        ThrottleKey::Username(username) => username,
+                        ThrottleKey::Ip(ip) => ip.to_string(),
+                    },
+                    failures: entry.failures,
+                    duration,
+                });
+            }
+        }
+        lockouts
+    }
+
+    fn lockout_for(&self, excess_failures: u32) -> Duration {
+        let seconds = self
+            .settings
+            .base_lockout_seconds
+            .saturating_mul(2u64.saturating_pow(excess_failures));
+        Duration::from_secs(seconds.min(self.settings.max_lockout_seconds))
+    }
+}
+
+fn normalise(username: &str) -> String {
+    username.trim().to_lowercase()
+}
+
+/// Records a failed attempt and writes any lockout it started to the audit log.
+pub async fn record_login_failure(
+    throttle: &LoginThrottle,
+    username: &str,
+    ip: Option<IpAddr>,
+    conn: &Surreal<Client>,
+    audit_context: &AuditContext,
+) {
+    for lockout in throttle.record_failure(username, ip) {
+        tracing::warn!(
+            key = %lockout.key,
+            failures = lockout.failures,
+            "Too many failed login attempts, locking out"
+        );
+        audit::record(
+            conn,
+            audit_context,
+            AuditEntry::new("login.lockout", username)
+                .target(lockout.key)
+                .diff(serde_json::json!({
+                    "failures": lockout.failures,
+                    "lockout_seconds": lockout.duration.as_secs(),
+                })),
+        )
+        .await;
+    }
+}
+// endregion: -- Login Throttle
+
+// region: -- Client IP
+/// The client IP the throttle keys on, taken from the `client_ip_header` a
+/// trusted proxy sets. `None` when no header is configured: behind a proxy
+/// the socket address is the proxy's, and keying on it would lock everyone
+/// out at once.
+#[derive(Clone, Copy, Debug)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+    LoginThrottle: FromRef<S>,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let throttle = LoginThrottle::from_ref(state);
+        Ok(Self(throttle.client_ip_header().and_then(|header| {
+            forwarded_client_ip(&parts.headers, header)
+        })))
+    }
+}
+
+/// The client IP a trusted proxy put in `header`.
+pub fn forwarded_client_ip(headers: &HeaderMap, header: &str) -> Option<IpAddr> {
+    // Proxies append to `X-Forwarded-For`, so only the last hop is trusted
+    headers
+        .get(header)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok())
+}
+// endregion: -- Client IP
+
+// region: -- Throttled Credential Validation
+/// `validate_credentials` behind the login throttle. A locked-out attempt is
+/// reported as `InvalidCredentials`, just like a wrong password.
+#[tracing::instrument(
+    name = "Validating throttled credentials",
+    skip(credentials, throttle, argon2, conn, audit_context)
+)]
+pub async fn validate_credentials_throttled(
+    credentials: Credentials,
+    ClientIp(client_ip): ClientIp,
+    throttle: &LoginThrottle,
+    argon2: &Argon2Settings,
+    conn: &Surreal<Client>,
+    audit_context: &AuditContext,
+) -> Result<Thing, AuthError> {
+    let username = credentials.username.clone();
+
+    if let Some(remaining) = throttle.check(&username, client_ip) {
+        return Err(AuthError::InvalidCredentials(color_eyre::eyre::eyre!(
+            "Locked out for another {}s.",
+            remaining.as_secs()
+        )));
+    }
+
+    match validate_credentials(credentials, argon2, conn).await {
+        Ok(user_id) => {
+            throttle.record_success(&username);
+            Ok(user_id)
+        }
+        Err(AuthError::InvalidCredentials(e)) => {
+            record_login_failure(throttle, &username, client_ip, conn, audit_context).await;
+            Err(AuthError::InvalidCredentials(e))
+        }
+        Err(e) => Err(e),
+    }
+}
+// endregion: -- Throttled Credential Validation
+
+#[cfg(test)]
+mod tests {
+    use super::LoginThrottle;
+    use crate::configuration::LoginThrottleSettings;
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::{Duration, Instant},
+    };
+
+    const IP: Option<IpAddr> = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+    const OTHER_IP: Option<IpAddr> = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+
+    fn throttle() -> LoginThrottle {
+        LoginThrottle::new(LoginThrottleSettings {
+            max_failures_per_username: 3,
+            max_failures_per_ip: 10,
+            base_lockout_seconds: 30,
+            max_lockout_seconds: 100,
+            ..LoginThrottleSettings::default()
+        })
+    }
+
+    #[test]
+    fn a_username_is_locked_after_too_many_failures() {
+        let throttle = throttle();
+        let now = Instant::now();
+        for _ in 0..2 {
+            throttle.record_failure_at("ursula", IP, now);
+        }
+        assert_eq!(throttle.check_at("ursula", IP, now), None);
+
+        throttle.record_failure_at("ursula", IP, now);
+        assert_eq!(
+            throttle.check_at("ursula", OTHER_IP, now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            throttle.check_at("Ursula", IP, now + Duration::from_secs(31)),
+            None
+        );
+    }
+
+    #[test]
+    fn lockouts_grow_exponentially_up_to_the_cap() {
+        let throttle = throttle();
+        let mut now = Instant::now();
+        let mut lockouts = Vec::new();
+        for _ in 0..6 {
+            let lockout = throttle
+                .record_failure_at("ursula", IP, now)
+                .into_iter()
+                .map(|lockout| lockout.duration.as_secs())
+                .next();
+            lockouts.extend(lockout);
+            now += Duration::from_secs(101);
+        }
+
+        assert_eq!(lockouts, vec![30, 60, 100, 100]);
+    }
+
+    #[test]
+    fn attempts_during_a_lockout_do_not_extend_it() {
+        let throttle = throttle();
+        let now = Instant::now();
+        for _ in 0..3 {
+            throttle.record_failure_at("ursula", None, now);
+        }
+
+        let later = now + Duration::from_secs(10);
+        for _ in 0..10 {
+            assert!(throttle.record_failure_at("ursula", None, later).is_empty());
+        }
+        assert_eq!(
+            throttle.check_at("ursula", None, later),
+            Some(Duration::from_secs(20))
+        );
+    }
+
+    #[test]
+    fn without_a_client_ip_only_the_username_is_counted() {
+        let throttle = throttle();
+        let now = Instant::now();
+        for i in 0..10 {
+            throttle.record_failure_at(&format!("user-{i}"), None, now);
+        }
+        assert!(throttle.check_at("someone-else", None, now).is_none());
+    }
+
+    #[test]
+    fn an_ip_is_locked_across_usernames() {
+        let throttle = throttle();
+        let now = Instant::now();
+        for i in 0..10 {
+            throttle.record_failure_at(&format!("user-{i}"), IP, now);
+        }
+        assert!(throttle.check_at("someone-else", IP, now).is_some());
+        assert!(throttle.check_at("someone-else", OTHER_IP, now).is_none());
+    }
+
+    #[test]
+    fn success_clears_the_username_but_not_the_ip() {
+        let throttle = throttle();
+        let now = Instant::now();
+        for _ in 0..2 {
+            throttle.record_failure_at("ursula", IP, now);
+        }
+        throttle.record_success("ursula");
+        throttle.record_failure_at("ursula", IP, now);
+        assert_eq!(throttle.check_at("ursula", IP, now), None);
+    }
+
+    #[test]
+    fn a_disabled_throttle_never_locks() {
+        let throttle = LoginThrottle::new(LoginThrottleSettings {
+            enabled: false,
+            max_failures_per_username: 1,
+            ..LoginThrottleSettings::default()
+        });
+        let now = Instant::now();
+        throttle.record_failure_at("ursula", IP, now);
+        assert_eq!(throttle.check_at("ursula", IP, now), None);
+    }
+}
@@ -0,0 +1,340 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use color_eyre::eyre::{eyre, Context};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use secrecy::{ExposeSecret, Secret};
+use sha2::{Digest, Sha256};
+use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+use totp_rs::{Algorithm, TOTP};
+
+use crate::error::{AuthError, UserError};
+
+const TOTP_ISSUER: &str = "zero2axum";
+const RECOVERY_CODE_COUNT: usize = 10;
+
+// region: -- TOTP
+/// Builds the RFC 6238 generator for a base32 encoded secret: SHA1, six
+/// digits, 30 second steps and one step of clock skew, which is what every
+/// authenticator app expects.
+pub fn totp_for(secret: &Secret<String>, username: &str) -> color_eyre::Result<TOTP> {
+    let secret = totp_rs::Secret::Encoded(secret.expose_secret().clone())
+        .to_bytes()
+        .map_err(|e| eyre!("Invalid TOTP secret: {:?}", e))?;
+
+    // The account name ends up in an otpauth URI label, where ':' is the separator
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret,
+        Some(TOTP_ISSUER.to_string()),
+        username.replace(':', "_"),
+    )
+    .map_err(|e| eyre!("Failed to build TOTP: {:?}", e))
+}
+
+fn generate_totp_secret() -> Secret<String> {
+    match totp_rs::Secret::generate_secret().to_encoded() {
+        totp_rs::Secret::Encoded(secret) => Secret::new(secret),
+        totp_rs::Secret::Raw(_) => unreachable!("to_encoded always returns Secret::Encoded"),
+    }
+}
+
+/// Returns the time step `code` was generated for, if it is within the
+/// allowed skew. Callers must reject steps at or below the last one accepted
+/// for the user, or an intercepted code could be replayed.
+fn check_totp(
+    secret: &Secret<String>,
+    username: &str,
+    code: &Secret<String>,
+) -> color_eyre::Result<Option<u64>> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("System clock is before the UNIX epoch")?
+        .as_secs();
+    Ok(matching_step(&totp_for(secret, username)?, code, now))
+}
+
+fn matching_step(totp: &TOTP, code: &Secret<String>, now: u64) -> Option<u64> {
+    let code = code.expose_secret().trim().as_bytes();
+    let current = now / totp.step;
+    let skew = u64::from(totp.skew);
+
+    (current.saturating_sub(skew)..=current + skew)
+        .find(|step| constant_time_eq(totp.generate(step * totp.step).as_bytes(), code))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+// endregion: -- TOTP
+
+// region: -- Enrollment
+pub struct TotpEnrollment {
+    pub secret: Secret<String>,
+    pub provisioning_uri: String,
+    /// PNG encoded QR code of `provisioning_uri`, base64 encoded.
+    pub qr_code: String,
+}
+
+/// Generates a new secret and parks it on the user until a code generated
+/// from it has been verified with `confirm_totp_enrollment`.
+#[tracing::instrument(name = "Begin TOTP enrollment", skip(user_id, conn))]
+pub async fn begin_totp_enrollment(
+    user_id: &Thing,
+    username: &str,
+    conn: &Surreal<Client>,
+) -> color_eyre::Result<TotpEnrollment> {
+    let secret = generate_totp_secret();
+    let totp = totp_for(&secret, username)?;
+    let qr_code = totp
+        .get_qr_base64()
+        .map_err(|e| eyre!("Failed to render QR code: {}", e))?;
+
+    let sql = "UPDATE $user_id SET totp_pending_secret = $secret";
+    conn.query(sql)
+        .bind(("user_id", user_id))
+        .bind(("secret", secret.expose_secret()))
+        .await
+        .context("Failed to store the pending TOTP secret")?
+        .check()?;
+
+    Ok(TotpEnrollment {
+        provisioning_uri: totp.get_url(),
+        secret,
+        qr_code,
+    })
+}
+
+/// Activates the pending secret if `code` matches it. Returns the freshly
+/// generated recovery codes, which are never shown again.
+#[tracing::instrument(name = "Confirm TOTP enrollment", skip(user_id, code, conn))]
+pub async fn confirm_totp_enrollment(
+    user_id: &Thing,
+    username: &str,
+    code: &Secret<String>,
+    conn: &Surreal<Client>,
+) -> color_eyre::Result<Option<Vec<Secret<String>>>> {
+    let mut res = conn
+        .query("SELECT totp_pending_secret FROM $user_id")
+        .bind(("user_id", user_id))
+        .await?
+        .check()?;
+    let pending: Option<String> = res.take((0, "totp_pending_secret"))?;
+    let Some(pending) = pending.map(Secret::new) else {
+        return Ok(None);
+    };
+
+    if check_totp(&pending, username, code)?.is_none() {
+        return Ok(None);
+    }
+
+    let recovery_codes = generate_recovery_codes();
+    let recovery_code_hashes: Vec<String> = recovery_codes.iter().map(hash_recovery_code).collect();
+
+    let sql = "UPDATE $user_id SET totp_secret = $secret, totp_pending_secret = NONE, \
+        totp_enabled_at = time::now(), recovery_codes = $recovery_codes";
+    conn.query(sql)
+        .bind(("user_id", user_id))
+        .bind(("secret", pending.expose_secret()))
+        .bind(("recovery_codes", recovery_code_hashes))
+        .await
+        .context("Failed to enable TOTP")?
+        .check()?;
+
+    Ok(Some(recovery_codes))
+}
+// endregion: -- Enrollment
+
+// region: -- Verification
+#[tracing::instrument(name = "Get TOTP secret", skip(user_id, conn))]
+pub async fn get_totp_secret(
+    user_id: &Thing,
+    conn: &Surreal<Client>,
+) -> color_eyre::Result<Option<Secret<String>>> {
+    let mut res = conn
+        .query("SELECT totp_secret FROM $user_id")
+        .bind(("user_id", user_id))
+        .await?
+        .check()?;
+    let secret: Option<String> = res.take((0, "totp_secret"))?;
+    Ok(secret.map(Secret::new))
+}
+
+/// Accepts either a current TOTP code or an unused recovery code. Both are
+/// single use: a TOTP code is rejected once a code for the same or a later
+/// time step has been accepted, and recovery codes are consumed.
+#[tracing::instrument(name = "Verify second factor", skip(user_id, code, conn))]
+pub async fn verify_second_factor(
+    user_id: &Thing,
+    username: &str,
+    code: Secret<String>,
+    conn: &Surreal<Client>,
+) -> Result<(), AuthError> {
+    let secret = get_totp_secret(user_id, conn)
+        .await?
+        .ok_or_else(|| eyre!("Two-factor authentication is not enabled."))
+        .map_err(AuthError::UnexpectedError)?;
+
+    if let Some(step) = check_totp(&secret, username, &code)? {
+        // Conditional, so two concurrent requests cannot both use the code
+        let sql = "UPDATE $user_id SET totp_last_step = $step \
+            WHERE totp_last_step = NONE OR totp_last_step < $step RETURN id";
+        let mut res = conn
+            .query(sql)
+            .bind(("user_id", user_id))
+            .bind(("step", step))
+            .await
+            .context("Failed to record the TOTP time step")?
+            .check()
+            .context("Failed to record the TOTP time step")?;
+        let accepted: Option<Thing> = res
+            .take((0, "id"))
+            .context("Failed to record the TOTP time step")?;
+
+        return match accepted {
+            Some(_) => Ok(()),
+            None => Err(AuthError::InvalidCredentials(eyre!(
+                "Second factor code has already been used."
+            ))),
+        };
+    }
+
+    let sql = "UPDATE $user_id SET recovery_codes -= $code_hash \
+        WHERE recovery_codes CONTAINS $code_hash RETURN id";
+    let mut res = conn
+        .query(sql)
+        .bind(("user_id", user_id))
+        .bind(("code_hash", hash_recovery_code(&code)))
+        .await
+        .context("Failed to consume a recovery code")?
+        .check()
+        .context("Failed to consume a recovery code")?;
+    let consumed: Option<Thing> = res
+        .take((0, "id"))
+        .context("Failed to consume a recovery code")?;
+
+    match consumed {
+        Some(_) => {
+            tracing::warn!(target: "audit", event = "login.recovery_code_used", "Recovery code used");
+            Ok(())
+        }
+        None => Err(AuthError::InvalidCredentials(eyre!(
+            "Invalid second factor code."
+        ))),
+    }
+}
+// endregion: -- Verification
+
+// region: -- Reset
+/// Removes TOTP and all recovery codes from a user, e.g. after they lost
+/// their device and their recovery codes.
+#[tracing::instrument(name = "Reset two-factor authentication", skip(conn))]
+pub async fn reset_two_factor(username: &str, conn: &Surreal<Client>) -> Result<Thing, UserError> {
+    let sql = "UPDATE users SET totp_secret = NONE, totp_pending_secret = NONE, \
+        totp_enabled_at = NONE, totp_last_step = NONE, recovery_codes = NONE \
+        WHERE username = $username";
+
+    let mut res = conn
+        .query(sql)
+        .bind(("username", username))
+        .await?
+        .check()?;
+
+    let user_id: Option<Thing> = res.take((0, "id"))?;
+    user_id.ok_or(UserError::UnknownUser)
+}
+// endregion: -- Reset
+
+// region: -- Recovery Codes
+fn generate_recovery_codes() -> Vec<Secret<String>> {
+    let mut rng = thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code: String = (&mut rng)
+                .sample_iter(Alphanumeric)
+                .map(char::from)
+                .map(|c| c.to_ascii_lowercase())
+                .take(10)
+                .collect();
+            Secret::new(format!("{}-{}", &code[..5], &code[5..]))
+        })
+        .collect()
+}
+
+/// Recovery codes carry 50 bits of entropy, so a plain SHA-256 is enough.
+/// Dashes, whitespace and case are ignored so users can type them sloppily.
+fn hash_recovery_code(code: &Secret<String>) -> String {
+    let normalised: String = code
+        .expose_secret()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    format!("{:x}", Sha256::digest(normalised.as_bytes()))
+}
+// endregion: -- Recovery Codes
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_totp, generate_recovery_codes, generate_totp_secret, hash_recovery_code,
+        matching_step, totp_for,
+    };
+    use secrecy::{ExposeSecret, Secret};
+
+    #[test]
+    fn a_current_code_is_accepted() {
+        let secret = generate_totp_secret();
+        let code = totp_for(&secret, "ursula")
+            .unwrap()
+            .generate_current()
+            .unwrap();
+        assert!(check_totp(&secret, "ursula", &Secret::new(code))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn a_code_is_matched_to_the_step_it_was_generated_for() {
+        let totp = totp_for(&generate_totp_secret(), "ursula").unwrap();
+        let now = 1_700_000_000;
+        let code = Secret::new(totp.generate(now - 30));
+
+        assert_eq!(matching_step(&totp, &code, now), Some(now / 30 - 1));
+        assert_eq!(matching_step(&totp, &code, now + 60), None);
+    }
+
+    #[test]
+    fn a_wrong_code_is_rejected() {
+        let secret = generate_totp_secret();
+        let code = totp_for(&secret, "ursula")
+            .unwrap()
+            .generate_current()
+            .unwrap();
+        let wrong = format!("{:06}", (code.parse::<u32>().unwrap() + 1) % 1_000_000);
+        assert!(check_totp(&secret, "ursula", &Secret::new(wrong))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn recovery_codes_are_unique() {
+        let codes = generate_recovery_codes();
+        let mut hashes: Vec<_> = codes.iter().map(hash_recovery_code).collect();
+        hashes.sort();
+        hashes.dedup();
+        assert_eq!(hashes.len(), codes.len());
+    }
+
+    #[test]
+    fn recovery_code_hashes_ignore_formatting() {
+        let code = generate_recovery_codes().remove(0);
+        let sloppy = Secret::new(format!(
+            " {} ",
+            code.expose_secret().replace('-', "").to_uppercase()
+        ));
+        assert_eq!(hash_recovery_code(&code), hash_recovery_code(&sloppy));
+    }
+}
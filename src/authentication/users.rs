@@ -0,0 +1,306 @@
+use color_eyre::eyre::Context;
+use secrecy::{ExposeSecret, Secret};
+use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    authentication::{compute_password_hash, get_user_role, Role},
+    configuration::{Argon2Settings, AuthenticationSettings, PasswordPolicySettings},
+    error::UserError,
+    telemetry::spawn_block_with_tracing,
+};
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct UserSummary {
+    pub id: String,
+    pub username: String,
+    pub role: Role,
+    pub deactivated: bool,
+}
+
+// region: -- Create User
+#[tracing::instrument(name = "Create user", skip(password, authentication, conn))]
+pub async fn create_user(
+    username: &str,
+    password: Secret<String>,
+    role: Role,
+    authentication: &AuthenticationSettings,
+    conn: &Surreal<Client>,
+) -> Result<Thing, UserError> {
+    validate_username(username)?;
+    validate_password(&password, &authentication.password_policy)?;
+
+    if get_user_id(username, conn).await?.is_some() {
+        return Err(UserError::UsernameTaken);
+    }
+
+    let password_hash = hash_password(password, &authentication.argon2).await?;
+    let sql = "CREATE users SET username = $username, password_hash = $password_hash, \
+        role = $role";
+
+    let mut res = conn
+        .query(sql)
+        .bind(("username", username))
+        .bind(("password_hash", password_hash.expose_secret()))
+        .bind(("role", role))
+        .await?
+        .check()?;
+
+    let user_id: Option<Thing> = res.take((0, "id"))?;
+    user_id
+        .ok_or_else(|| color_eyre::eyre::eyre!("The new user was not returned by SurrealDB."))
+        .map_err(UserError::UnexpectedError)
+}
+// endregion: -- Create User
+
+// region: -- Change Password
+#[tracing::instrument(name = "Change password", skip(password, authentication, conn))]
+pub async fn change_password(
+    username: &str,
+    password: Secret<String>,
+    authentication: &AuthenticationSettings,
+    conn: &Surreal<Client>,
+) -> Result<(), UserError> {
+    validate_password(&password, &authentication.password_policy)?;
+
+    let password_hash = hash_password(password, &authentication.argon2).await?;
+    let sql = "UPDATE users SET password_hash = $password_hash WHERE username = $username";
+
+    let mut res = conn
+        .query(sql)
+        .bind(("username", username))
+        .bind(("password_hash", password_hash.expose_secret()))
+        .await?
+        .check()?;
+
+    let user_id: Option<Thing> = res.take((0, "id"))?;
+    user_id.map(|_| ()).ok_or(UserError::UnknownUser)
+}
+// endregion: -- Change Password
+
+// region: -- Set Role
+/// Changes a user's role and returns the previous one. The last active owner
+/// cannot be demoted, otherwise nobody could manage users any more.
+#[tracing::instrument(name = "Set user role", skip(conn))]
+pub async fn set_user_role(
+    username: &str,
+    role: Role,
+    conn: &Surreal<Client>,
+) -> Result<Role, UserError> {
+    let user_id = get_user_id(username, conn)
+        .await?
+        .ok_or(UserError::UnknownUser)?;
+    let previous = get_user_role(&user_id, conn).await?;
+
+    if previous == Role::Owner && role != Role::Owner {
+        let sql = "SELECT count() AS owners FROM users \
+            WHERE role = 'owner' AND deactivated_at = NONE AND id != $user_id GROUP ALL";
+        let mut res = conn.query(sql).bind(("user_id", &user_id)).await?.check()?;
+        let other_owners: Option<i64> = res.take((0, "owners"))?;
+
+        if other_owners.unwrap_or(0) == 0 {
+            return Err(UserError::ValidationError(
+                "The last owner cannot be demoted.".into(),
+            ));
+        }
+    }
+
+    conn.query("UPDATE $user_id SET role = $role")
+        .bind(("user_id", &user_id))
+        .bind(("role", role))
+        .await?
+        .check()?;
+
+    Ok(previous)
+}
+// endregion: -- Set Role
+
+// region: -- Deactivate User
+#[tracing::instrument(name = "Deactivate user", skip(conn))]
+pub async fn deactivate_user(username: &str, conn: &Surreal<Client>) -> Result<Thing, UserError> {
+    let sql = "UPDATE users SET deactivated_at = time::now() WHERE username = $username";
+
+    let mut res = conn
+        .query(sql)
+        .bind(("username", username))
+        .await?
+        .check()?;
+
+    let user_id: Option<Thing> = res.take((0, "id"))?;
+    user_id.ok_or(UserError::UnknownUser)
+}
+// endregion: -- Deactivate User
+
+// region: -- Delete User
+#[tracing::instrument(name = "Delete user", skip(conn))]
+pub async fn delete_user(username: &str, conn: &Surreal<Client>) -> Result<(), UserError> {
+    let sql = "DELETE users WHERE username = $username RETURN BEFORE";
+
+    let mut res = conn
+        .query(sql)
+        .bind(("username", username))
+        .await?
+        .check()?;
+
+    let user_id: Option<Thing> = res.take((0, "id"))?;
+    user_id.map(|_| ()).ok_or(UserError::UnknownUser)
+}
+// endregion: -- Delete User
+
+// region: -- List Users
+#[tracing::instrument(name = "List users", skip(conn))]
+pub async fn list_users(conn: &Surreal<Client>) -> Result<Vec<UserSummary>, UserError> {
+    let sql = "SELECT <string> id AS id, username, role, deactivated_at != NONE AS deactivated \
+        FROM users ORDER BY username";
+
+    let mut res = conn.query(sql).await?.check()?;
+    let users: Vec<UserSummary> = res.take(0)?;
+    Ok(users)
+}
+// endregion: -- List Users
+
+// region: -- Helpers
+#[tracing::instrument(name = "Get user id", skip(conn))]
+pub async fn get_user_id(
+    username: &str,
+    conn: &Surreal<Client>,
+) -> Result<Option<Thing>, UserError> {
+    let sql = "SELECT id FROM users WHERE username = $username";
+
+    let mut res = conn
+        .query(sql)
+        .bind(("username", username))
+        .await?
+        .check()?;
+
+    let user_id: Option<Thing> = res.take((0, "id"))?;
+    Ok(user_id)
+}
+
+async fn hash_password(
+    password: Secret<String>,
+    argon2: &Argon2Settings,
+) -> Result<Secret<String>, UserError> {
+    let argon2 = argon2.clone();
+    let password_hash = spawn_block_with_tracing(move || compute_password_hash(password, &argon2))
+        .await
+        .context("Failed to spawn blocking task")??;
+    Ok(password_hash)
+}
+
+fn validate_username(username: &str) -> Result<(), UserError> {
+    let is_empty_or_whitespace = username.trim().is_empty();
+    let is_too_long = username.graphemes(true).count() > 64;
+    let contains_whitespace = username.chars().any(char::is_whitespace);
+
+    if is_empty_or_whitespace || is_too_long || contains_whitespace {
+        Err(UserError::ValidationError(format!(
+            "{} is not a valid username.",
+            username
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn validate_password(
+    password: &Secret<String>,
+    policy: &PasswordPolicySettings,
+) -> Result<(), UserError> {
+    let password = password.expose_secret();
+    let length = password.graphemes(true).count();
+
+    let problem = if length < policy.min_length {
+        Some(format!(
+            "The password must be at least {} characters long.",
+            policy.min_length
+        ))
+    } else if length > policy.max_length {
+        Some(format!(
+            "The password must be at most {} characters long.",
+            policy.max_length
+        ))
+    } else if policy.require_mixed_case
+        && !(password.chars().any(char::is_uppercase) && password.chars().any(char::is_lowercase))
+    {
+        Some("The password must contain both upper and lower case letters.".to_string())
+    } else if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+        Some("The password must contain at least one digit.".to_string())
+    } else {
+        None
+    };
+
+    match problem {
+        Some(message) => Err(UserError::ValidationError(message)),
+        None => Ok(()),
+    }
+}
+// endregion: -- Helpers
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_password, validate_username};
+    use crate::configuration::PasswordPolicySettings;
+    use claims::{assert_err, assert_ok};
+    use secrecy::Secret;
+
+    #[test]
+    fn a_simple_username_is_valid() {
+        assert_ok!(validate_username("ursula"));
+    }
+
+    #[test]
+    fn usernames_with_whitespace_are_rejected() {
+        assert_err!(validate_username("ursula le guin"));
+        assert_err!(validate_username(" "));
+        assert_err!(validate_username(""));
+    }
+
+    #[test]
+    fn a_username_longer_than_64_graphemes_is_rejected() {
+        assert_err!(validate_username(&"a".repeat(65)));
+    }
+
+    #[test]
+    fn passwords_shorter_than_the_policy_minimum_are_rejected() {
+        let policy = PasswordPolicySettings::default();
+        assert_err!(validate_password(
+            &Secret::new("a".repeat(policy.min_length - 1)),
+            &policy
+        ));
+        assert_ok!(validate_password(
+            &Secret::new("a".repeat(policy.min_length)),
+            &policy
+        ));
+    }
+
+    #[test]
+    fn passwords_longer_than_the_policy_maximum_are_rejected() {
+        let policy = PasswordPolicySettings::default();
+        assert_err!(validate_password(
+            &Secret::new("a".repeat(policy.max_length + 1)),
+            &policy
+        ));
+    }
+
+    #[test]
+    fn optional_character_classes_are_enforced() {
+        let policy = PasswordPolicySettings {
+            require_mixed_case: true,
+            require_digit: true,
+            ..PasswordPolicySettings::default()
+        };
+        assert_err!(validate_password(
+            &Secret::new("alllowercase1".into()),
+            &policy
+        ));
+        assert_err!(validate_password(
+            &Secret::new("MixedCaseOnly".into()),
+            &policy
+        ));
+        assert_ok!(validate_password(
+            &Secret::new("MixedCase1234".into()),
+            &policy
+        ));
+    }
+}
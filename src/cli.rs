@@ -0,0 +1,505 @@
+use std::io::{BufRead, IsTerminal};
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use color_eyre::eyre::{eyre, Context};
+use secrecy::Secret;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+use tracing::info;
+
+use crate::{
+    audit::{self, change, AuditContext, AuditEntry},
+    authentication::{
+        change_password, create_user, deactivate_user, delete_user, reset_two_factor,
+        set_user_role, Role,
+    },
+    configuration::{get_configuration, Settings},
+    db::Database,
+    domain::{NewSubscriber, SubscriberEmail},
+    session::build_session_store,
+    startup::Application,
+};
+
+// region: -- CLI Definition
+#[derive(Parser, Debug)]
+#[command(name = "zero2axum", version, about = "Zero To Axum newsletter service")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Start the HTTP server (default)
+    Serve,
+    /// Apply pending SurrealDB migrations
+    Migrate,
+    /// Manage users
+    User {
+        #[command(subcommand)]
+        command: UserCommand,
+    },
+    /// Export or import subscribers
+    Subscribers {
+        #[command(subcommand)]
+        command: SubscribersCommand,
+    },
+    /// Inspect configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Send a test email through the configured email provider
+    SendTestEmail {
+        /// Recipient address
+        #[arg(long)]
+        to: String,
+        #[arg(long, default_value = "zero2axum test email")]
+        subject: String,
+    },
+    /// Show or change the log filter of a running server
+    LogLevel {
+        /// New `RUST_LOG` style directive, e.g. `info,zero2axum=debug`
+        directive: Option<String>,
+        /// Restore the startup directive after this many seconds
+        #[arg(long, requires = "directive")]
+        ttl_seconds: Option<u64>,
+        /// Restore the startup directive now
+        #[arg(long, conflicts_with = "directive")]
+        reset: bool,
+        /// Base URL of the server (defaults to `application.base_url`)
+        #[arg(long)]
+        url: Option<String>,
+        /// API token with the `logging:write` scope, owned by an owner
+        #[arg(long, env = "ZERO2AXUM_API_TOKEN", hide_env_values = true)]
+        token: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum UserCommand {
+    /// Create a new user (prompts for the password, or reads ZERO2AXUM_PASSWORD or stdin)
+    Create {
+        username: String,
+        /// owner, editor or viewer
+        #[arg(long, default_value = "viewer")]
+        role: Role,
+    },
+    /// Replace a user's password (prompts for it, or reads ZERO2AXUM_PASSWORD or stdin)
+    SetPassword { username: String },
+    /// Change a user's role (owner, editor or viewer)
+    SetRole { username: String, role: Role },
+    /// Remove a user's TOTP secret and recovery codes and end their sessions
+    #[command(name = "reset-2fa")]
+    ResetTwoFactor { username: String },
+    /// Deactivate a user without deleting them
+    Deactivate { username: String },
+    /// Delete a user
+    Delete { username: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SubscribersCommand {
+    /// Write all subscribers as JSON to a file (or stdout)
+    Export {
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Read subscribers from a JSON file (or stdin), skipping existing emails
+    Import {
+        #[arg(long, short)]
+        input: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Load and validate the configuration, then try to reach SurrealDB
+    Check,
+}
+
+impl Cli {
+    pub fn is_serve(&self) -> bool {
+        matches!(self.command, None | Some(Command::Serve))
+    }
+
+    pub async fn run(self) -> color_eyre::Result<()> {
+        let configuration = get_configuration().context("Failed to read configuration.")?;
+
+        match self.command.unwrap_or(Command::Serve) {
+            Command::Serve => serve(configuration).await,
+            Command::Migrate => {
+                let database = connect(&configuration).await?;
+                database
+                    .migrate(&configuration)
+                    .await
+                    .map_err(|e| eyre!(e).wrap_err("Failed to migrate the database."))?;
+                println!("Migrations applied.");
+                Ok(())
+            }
+            Command::User { command } => {
+                let database = connect(&configuration).await?;
+                run_user_command(command, &configuration, &database).await
+            }
+            Command::Subscribers { command } => {
+                let database = connect(&configuration).await?;
+                run_subscribers_command(command, &database).await
+            }
+            Command::Config {
+                command: ConfigCommand::Check,
+            } => check_configuration(&configuration).await,
+            Command::SendTestEmail { to, subject } => {
+                send_test_email(&configuration, to, &subject).await
+            }
+            Command::LogLevel {
+                directive,
+                ttl_seconds,
+                reset,
+                url,
+                token,
+            } => {
+                let url = url.unwrap_or(configuration.application.base_url);
+                log_level(&url, &token, directive, ttl_seconds, reset).await
+            }
+        }
+    }
+}
+// endregion: -- CLI Definition
+
+// region: -- Serve
+async fn serve(configuration: Settings) -> color_eyre::Result<()> {
+    let database = connect(&configuration).await?;
+
+    let application = Application::build(configuration.clone(), database)
+        .await
+        .context("Application Failed to Start")?;
+    info!(
+        "Server listening on http://{}:{}",
+        configuration.application.host, configuration.application.port
+    );
+    application.run_until_stopped().await
+}
+
+async fn connect(configuration: &Settings) -> color_eyre::Result<Database> {
+    Database::new(configuration)
+        .await
+        .context("Application failed to create SurrealDB")
+}
+// endregion: -- Serve
+
+// region: -- User Commands
+async fn run_user_command(
+    command: UserCommand,
+    configuration: &Settings,
+    database: &Database,
+) -> color_eyre::Result<()> {
+    let client = &database.client;
+    let authentication = &configuration.authentication;
+
+    let entry = match command {
+        UserCommand::Create { username, role } => {
+            let user_id =
+                create_user(&username, read_password()?, role, authentication, client).await?;
+            println!("Created {} '{}' ({}).", role, username, user_id);
+            AuditEntry::new("user.create", "cli")
+                .target(username)
+                .diff(change("role", None::<Role>, role))
+        }
+        UserCommand::SetRole { username, role } => {
+            let previous = set_user_role(&username, role, client).await?;
+            println!("'{}' is now a {}.", username, role);
+            AuditEntry::new("user.role_change", "cli")
+                .target(username)
+                .diff(change("role", previous, role))
+        }
+        UserCommand::SetPassword { username } => {
+            change_password(&username, read_password()?, authentication, client).await?;
+            println!("Password updated for '{}'.", username);
+            AuditEntry::new("user.password_change", "cli").target(username)
+        }
+        UserCommand::ResetTwoFactor { username } => {
+            let user_id = reset_two_factor(&username, client).await?;
+            build_session_store(configuration, database)
+                .await?
+                .delete_for_user(&user_id, None)
+                .await?;
+            println!("Reset two-factor authentication for '{}'.", username);
+            AuditEntry::new("user.two_factor_reset", "cli").target(username)
+        }
+        UserCommand::Deactivate { username } => {
+            let user_id = deactivate_user(&username, client).await?;
+            build_session_store(configuration, database)
+                .await?
+                .delete_for_user(&user_id, None)
+                .await?;
+            println!("Deactivated user '{}'.", username);
+            AuditEntry::new("user.deactivate", "cli")
+                .target(username)
+                .diff(change("active", true, false))
+        }
+        UserCommand::Delete { username } => {
+            delete_user(&username, client).await?;
+            println!("Deleted user '{}'.", username);
+            AuditEntry::new("user.delete", "cli").target(username)
+        }
+    };
+    audit::record(client, &AuditContext::cli(), entry).await;
+
+    Ok(())
+}
+
+const PASSWORD_ENV: &str = "ZERO2AXUM_PASSWORD";
+
+/// Non-interactive runs set `ZERO2AXUM_PASSWORD` or pipe the password in on
+/// stdin; on a terminal it is prompted for without echo. Never taken as an
+/// argument, where it would show up in `ps` and shell history.
+fn read_password() -> color_eyre::Result<Secret<String>> {
+    let password = match std::env::var(PASSWORD_ENV) {
+        Ok(password) => password,
+        Err(_) if std::io::stdin().is_terminal() => {
+            rpassword::prompt_password("Password: ").context("Failed to read the password")?
+        }
+        Err(_) => {
+            let mut line = String::new();
+            std::io::stdin().lock().read_line(&mut line)?;
+            line.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+
+    if password.is_empty() {
+        return Err(eyre!("Password must not be empty."));
+    }
+    Ok(Secret::new(password))
+}
+// endregion: -- User Commands
+
+// region: -- Subscriber Commands
+const SUBSCRIBER_STATUSES: [&str; 2] = ["pending_confirmation", "confirmed"];
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SubscriberRecord {
+    email: String,
+    name: String,
+    status: String,
+    subscribed_at: String,
+}
+
+impl SubscriberRecord {
+    /// Checks an imported row against what `subscriptions` readers expect,
+    /// returning `subscribed_at` as a timestamp or every reason it was rejected.
+    fn validate(&self) -> Result<DateTime<Utc>, Vec<String>> {
+        let mut reasons = match NewSubscriber::parse(self.name.clone(), self.email.clone()) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|e| e.message).collect(),
+        };
+        if !SUBSCRIBER_STATUSES.contains(&self.status.as_str()) {
+            reasons.push(format!(
+                "Unknown status `{}`, expected one of {}.",
+                self.status,
+                SUBSCRIBER_STATUSES.join(", ")
+            ));
+        }
+        let subscribed_at = DateTime::parse_from_rfc3339(&self.subscribed_at)
+            .map(|timestamp| timestamp.with_timezone(&Utc))
+            .map_err(|e| {
+                reasons.push(format!(
+                    "Invalid subscribed_at `{}`: {}.",
+                    self.subscribed_at, e
+                ))
+            });
+
+        match subscribed_at {
+            Ok(subscribed_at) if reasons.is_empty() => Ok(subscribed_at),
+            _ => Err(reasons),
+        }
+    }
+}
+
+async fn run_subscribers_command(
+    command: SubscribersCommand,
+    database: &Database,
+) -> color_eyre::Result<()> {
+    let client = &database.client;
+
+    match command {
+        SubscribersCommand::Export { output } => {
+            let sql = "SELECT email, name, status, <string> subscribed_at AS subscribed_at \
+                FROM subscriptions ORDER BY subscribed_at";
+            let mut res = client.query(sql).await?.check()?;
+            let subscribers: Vec<SubscriberRecord> = res.take(0)?;
+
+            let json = serde_json::to_string_pretty(&subscribers)?;
+            match output {
+                Some(path) => std::fs::write(&path, json)
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None => println!("{}", json),
+            }
+            eprintln!("Exported {} subscribers.", subscribers.len());
+        }
+        SubscribersCommand::Import { input } => {
+            let json = match input {
+                Some(path) => std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?,
+                None => std::io::read_to_string(std::io::stdin())?,
+            };
+            let subscribers: Vec<SubscriberRecord> =
+                serde_json::from_str(&json).context("Invalid subscriber export file.")?;
+
+            let (mut imported, mut skipped, mut rejected) = (0, 0, 0);
+            for (row, subscriber) in subscribers.iter().enumerate() {
+                let subscribed_at = match subscriber.validate() {
+                    Ok(subscribed_at) => subscribed_at,
+                    Err(reasons) => {
+                        eprintln!(
+                            "Rejected row {} ({}): {}",
+                            row + 1,
+                            subscriber.email,
+                            reasons.join(" ")
+                        );
+                        rejected += 1;
+                        continue;
+                    }
+                };
+
+                let mut res = client
+                    .query("SELECT id FROM subscriptions WHERE email = $email")
+                    .bind(("email", &subscriber.email))
+                    .await?
+                    .check()?;
+                let existing: Option<Thing> = res.take((0, "id"))?;
+                if existing.is_some() {
+                    skipped += 1;
+                    continue;
+                }
+
+                let sql = "CREATE subscriptions SET email = $email, name = $name, \
+                    status = $status, subscribed_at = <datetime> $subscribed_at";
+                client
+                    .query(sql)
+                    .bind(("email", &subscriber.email))
+                    .bind(("name", &subscriber.name))
+                    .bind(("status", &subscriber.status))
+                    .bind(("subscribed_at", subscribed_at.to_rfc3339()))
+                    .await?
+                    .check()
+                    .with_context(|| format!("Failed to import {}", subscriber.email))?;
+                imported += 1;
+            }
+            println!(
+                "Imported {} subscribers ({} already present, {} rejected).",
+                imported, skipped, rejected
+            );
+        }
+    }
+
+    Ok(())
+}
+// endregion: -- Subscriber Commands
+
+// region: -- Config Check
+async fn check_configuration(configuration: &Settings) -> color_eyre::Result<()> {
+    println!("{:#?}", configuration);
+
+    configuration
+        .email_client
+        .sender()
+        .map_err(|e| eyre!("email_client.sender_email: {}", e))?;
+    println!("email_client: ok");
+
+    connect(configuration).await?;
+    println!("database: ok");
+
+    Ok(())
+}
+// endregion: -- Config Check
+
+// region: -- Send Test Email
+async fn send_test_email(
+    configuration: &Settings,
+    to: String,
+    subject: &str,
+) -> color_eyre::Result<()> {
+    let recipient = SubscriberEmail::parse(to).map_err(|e| eyre!(e))?;
+    let email_client = configuration.email_client.client();
+
+    email_client
+        .send_email(
+            &recipient,
+            subject,
+            "<p>This is a test email sent by <code>zero2axum send-test-email</code>.</p>",
+            "This is a test email sent by `zero2axum send-test-email`.",
+        )
+        .await
+        .context("Failed to send test email.")?;
+
+    println!("Sent test email to {}.", recipient);
+    Ok(())
+}
+// endregion: -- Send Test Email
+
+// region: -- Log Level
+async fn log_level(
+    url: &str,
+    token: &str,
+    directive: Option<String>,
+    ttl_seconds: Option<u64>,
+    reset: bool,
+) -> color_eyre::Result<()> {
+    let url = format!("{}/admin/log-level", url.trim_end_matches('/'));
+    let client = reqwest::Client::new();
+
+    let request = match (directive, reset) {
+        (None, false) => client.get(&url),
+        (directive, reset) => client.put(&url).json(&serde_json::json!({
+            "directive": directive,
+            "ttl_seconds": ttl_seconds,
+            "reset": reset,
+        })),
+    };
+    let response = request
+        .bearer_auth(token)
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach {}", url))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(eyre!("The server responded with {}.", status));
+    }
+    let body: serde_json::Value = response.json().await?;
+    println!("{}", serde_json::to_string_pretty(&body)?);
+    Ok(())
+}
+// endregion: -- Log Level
+
+#[cfg(test)]
+mod tests {
+    use super::SubscriberRecord;
+
+    fn record(status: &str, subscribed_at: &str) -> SubscriberRecord {
+        SubscriberRecord {
+            email: "ursula_le_guin@gmail.com".into(),
+            name: "Ursula Le Guin".into(),
+            status: status.into(),
+            subscribed_at: subscribed_at.into(),
+        }
+    }
+
+    #[test]
+    fn exported_rows_are_accepted() {
+        assert!(record("confirmed", "2023-06-28T10:17:11.123456Z")
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn unknown_statuses_and_bad_timestamps_are_rejected() {
+        let reasons = record("subscribed", "last tuesday").validate().unwrap_err();
+
+        assert_eq!(reasons.len(), 2);
+        assert!(reasons[0].contains("subscribed"));
+        assert!(reasons[1].contains("last tuesday"));
+    }
+}
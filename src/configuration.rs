@@ -1,15 +1,341 @@
+use std::collections::HashMap;
+
+use argon2::{Algorithm, Argon2, Params, Version};
 use secrecy::{ExposeSecret, Secret};
-use serde_aux::field_attributes::deserialize_number_from_string;
+use serde_aux::field_attributes::{
+    deserialize_number_from_string, deserialize_option_number_from_string,
+    deserialize_vec_from_string_or_vec,
+};
 use surrealdb_migrations::SurrealdbConfiguration;
 
-use crate::domain::SubscriberEmail;
+use crate::{
+    domain::{SubscriberEmail, SubscriberEmailError},
+    email_client::EmailClient,
+    redaction::{DEFAULT_ALLOWED_FIELDS, DEFAULT_DROPPED_FIELDS},
+};
 
 #[derive(serde::Deserialize, Clone, Debug)]
 pub struct Settings {
     pub database: DatabaseSettings,
     pub application: ApplicationSettings,
     pub email_client: EmailClientSettings,
-    // pub redis_uri: Secret<String>,
+    #[serde(default)]
+    pub authentication: AuthenticationSettings,
+    #[serde(default)]
+    pub session: SessionSettings,
+    #[serde(default)]
+    pub security_headers: SecurityHeadersSettings,
+    #[serde(default)]
+    pub telemetry: TelemetrySettings,
+    #[serde(default)]
+    pub metrics: MetricsSettings,
+    #[serde(default)]
+    pub health: HealthSettings,
+    #[serde(default)]
+    pub sentry: SentrySettings,
+    #[serde(default)]
+    pub redaction: RedactionSettings,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SessionSettings {
+    pub backend: SessionBackend,
+    pub redis_uri: Option<Secret<String>>,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub ttl_seconds: u64,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            backend: SessionBackend::Surrealdb,
+            redis_uri: None,
+            ttl_seconds: 60 * 60,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionBackend {
+    Redis,
+    Surrealdb,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct AuthenticationSettings {
+    pub argon2: Argon2Settings,
+    pub password_policy: PasswordPolicySettings,
+    pub throttle: LoginThrottleSettings,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LoginThrottleSettings {
+    pub enabled: bool,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_failures_per_username: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_failures_per_ip: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub base_lockout_seconds: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_lockout_seconds: u64,
+    /// Failures older than this are forgotten.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub window_seconds: u64,
+    /// Header a trusted reverse proxy sets to the client IP, e.g.
+    /// `Fly-Client-IP`. Without one, failures are only counted per username.
+    pub client_ip_header: Option<String>,
+}
+
+impl Default for LoginThrottleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_failures_per_username: 5,
+            max_failures_per_ip: 20,
+            base_lockout_seconds: 30,
+            max_lockout_seconds: 15 * 60,
+            window_seconds: 15 * 60,
+            client_ip_header: None,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PasswordPolicySettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub min_length: usize,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_length: usize,
+    pub require_mixed_case: bool,
+    pub require_digit: bool,
+}
+
+impl Default for PasswordPolicySettings {
+    fn default() -> Self {
+        Self {
+            min_length: 12,
+            max_length: 128,
+            require_mixed_case: false,
+            require_digit: false,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Argon2Settings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub memory_size_kib: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub iterations: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub parallelism: u32,
+}
+
+impl Argon2Settings {
+    pub fn params(&self) -> Result<Params, argon2::Error> {
+        Params::new(
+            self.memory_size_kib,
+            self.iterations,
+            self.parallelism,
+            None,
+        )
+    }
+
+    pub fn hasher(&self) -> Result<Argon2<'static>, argon2::Error> {
+        Ok(Argon2::new(
+            Algorithm::Argon2id,
+            Version::V0x13,
+            self.params()?,
+        ))
+    }
+}
+
+impl Default for Argon2Settings {
+    fn default() -> Self {
+        Self {
+            memory_size_kib: 15000,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+const DEFAULT_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; img-src 'self'; \
+    style-src 'self' 'nonce-{nonce}'; script-src 'self'; object-src 'none'; \
+    frame-ancestors 'none'; base-uri 'self'; form-action 'self'";
+
+/// An empty string leaves the header out.
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SecurityHeadersSettings {
+    pub enabled: bool,
+    /// `{nonce}` is replaced with the nonce of the current request.
+    pub content_security_policy: String,
+    /// Zero leaves out `Strict-Transport-Security`.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub hsts_max_age_seconds: u64,
+    pub hsts_include_subdomains: bool,
+    pub frame_options: String,
+    pub referrer_policy: String,
+    pub permissions_policy: String,
+    /// Route path (as registered, e.g. `/admin/tokens/:token_id`) to header
+    /// name to value, replacing the defaults above for that route.
+    pub overrides: HashMap<String, HashMap<String, String>>,
+}
+
+impl Default for SecurityHeadersSettings {
+    fn default() -> Self {
+        // The QR code on the two-factor page is an inline `data:` image
+        let two_factor_policy =
+            DEFAULT_CONTENT_SECURITY_POLICY.replace("img-src 'self'", "img-src 'self' data:");
+
+        Self {
+            enabled: true,
+            content_security_policy: DEFAULT_CONTENT_SECURITY_POLICY.to_string(),
+            hsts_max_age_seconds: 365 * 24 * 60 * 60,
+            hsts_include_subdomains: true,
+            frame_options: "DENY".to_string(),
+            referrer_policy: "strict-origin-when-cross-origin".to_string(),
+            permissions_policy: "camera=(), microphone=(), geolocation=(), payment=()".to_string(),
+            overrides: HashMap::from([(
+                "/admin/two-factor".to_string(),
+                HashMap::from([("content-security-policy".to_string(), two_factor_policy)]),
+            )]),
+        }
+    }
+}
+
+/// Spans are exported over OTLP/gRPC only when `otlp_endpoint` is set
+/// (e.g. `http://localhost:4317`).
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    pub otlp_endpoint: Option<String>,
+    /// Share of new traces to record, from 0.0 to 1.0.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub sampling_ratio: f64,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            sampling_ratio: 1.0,
+        }
+    }
+}
+
+/// With a `port`, `/metrics` is served only on that port (bound to
+/// `application.host`) instead of alongside the application routes. The
+/// default is port 9091, so metrics are never public by accident; a null
+/// `port` serves them on the application port, which only makes sense behind
+/// a proxy that authenticates `/metrics`.
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MetricsSettings {
+    pub enabled: bool,
+    #[serde(deserialize_with = "deserialize_option_number_from_string")]
+    pub port: Option<u16>,
+}
+
+impl Default for MetricsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            port: Some(9091),
+        }
+    }
+}
+
+/// Readiness probes give up after `timeout_milliseconds` each. The email
+/// provider is only probed when `probe_email` is set.
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct HealthSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub timeout_milliseconds: u64,
+    pub probe_email: bool,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        Self {
+            timeout_milliseconds: 2000,
+            probe_email: false,
+        }
+    }
+}
+
+impl HealthSettings {
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.timeout_milliseconds)
+    }
+}
+
+/// Errors are reported to Sentry only when `dsn` is set. `environment`
+/// defaults to `APP_ENVIRONMENT`.
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SentrySettings {
+    pub dsn: Option<Secret<String>>,
+    pub environment: Option<String>,
+    /// Share of requests to send as performance transactions, from 0.0 to 1.0.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub traces_sample_rate: f32,
+}
+
+/// What tracing output (logs, exported spans, Sentry) may contain; see
+/// `redaction::RedactionPolicy`. Setting either list replaces its default;
+/// from the environment they are comma separated, e.g.
+/// `APP_REDACTION__ALLOWED_FIELDS=request_id,status`.
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RedactionSettings {
+    pub enabled: bool,
+    pub emails: EmailRedaction,
+    /// Keys the email hash. Without one a random key is drawn at startup, so
+    /// hashes only match within a single process.
+    pub hash_key: Option<Secret<String>>,
+    #[serde(deserialize_with = "deserialize_vec_from_string_or_vec")]
+    pub allowed_fields: Vec<String>,
+    #[serde(deserialize_with = "deserialize_vec_from_string_or_vec")]
+    pub dropped_fields: Vec<String>,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            emails: EmailRedaction::Hash,
+            hash_key: None,
+            allowed_fields: DEFAULT_ALLOWED_FIELDS
+                .iter()
+                .map(|f| f.to_string())
+                .collect(),
+            dropped_fields: DEFAULT_DROPPED_FIELDS
+                .iter()
+                .map(|f| f.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailRedaction {
+    /// A short HMAC-SHA256 prefix keyed by `hash_key`, so one address can
+    /// still be followed without being guessable from a list of candidates.
+    Hash,
+    /// Keeps the first character and the domain.
+    Mask,
+    Keep,
 }
 
 #[derive(serde::Deserialize, Clone, Debug)]
@@ -21,7 +347,17 @@ pub struct EmailClientSettings {
 }
 
 impl EmailClientSettings {
-    pub fn sender(&self) -> Result<SubscriberEmail, String> {
+    pub fn client(&self) -> EmailClient {
+        let sender_email = self.sender().expect("Invalid sender email address.");
+        EmailClient::new(
+            self.base_url.clone(),
+            sender_email,
+            self.authorization_token.clone(),
+            self.timeout(),
+        )
+    }
+
+    pub fn sender(&self) -> Result<SubscriberEmail, SubscriberEmailError> {
         SubscriberEmail::parse(self.sender_email.clone())
     }
 
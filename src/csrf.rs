@@ -0,0 +1,238 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::{header, request::Parts, HeaderMap, Method, Request},
+    middleware::Next,
+    response::Response,
+};
+use color_eyre::eyre::eyre;
+use http_body::{LengthLimitError, Limited};
+use hyper::Body;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use secrecy::ExposeSecret;
+use tower_cookies::{cookie::SameSite, Cookie, Cookies, Key};
+
+use crate::{error::CsrfError, session::SESSION_COOKIE, startup::HmacSecret};
+
+pub const CSRF_COOKIE: &str = "_csrf";
+pub const CSRF_FIELD: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// Form bodies are buffered before authentication to find the token, so they
+/// are capped well below the extractors' limit. A newsletter issue is the
+/// largest form we serve.
+const MAX_FORM_BODY_BYTES: usize = 256 * 1024;
+
+// region: -- CSRF Token
+/// The double-submit token for the current client. Render it into every form
+/// as a hidden `csrf_token` field.
+#[derive(Clone, Debug)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for CsrfToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for CsrfToken {
+    type Rejection = CsrfError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<CsrfToken>()
+            .cloned()
+            .ok_or_else(|| eyre!("The CSRF middleware is not installed on this route.").into())
+    }
+}
+
+fn generate_csrf_token() -> String {
+    let mut rng = thread_rng();
+    std::iter::repeat_with(|| rng.sample(Alphanumeric))
+        .map(char::from)
+        .take(32)
+        .collect()
+}
+// endregion: -- CSRF Token
+
+// region: -- CSRF Middleware
+/// Issues a token in a cookie signed with the `HmacSecret` key and, for
+/// unsafe methods, requires the same token back in the `csrf_token` form
+/// field or the `X-CSRF-Token` header.
+///
+/// Requests carrying a Bearer token are exempt: a cross-site page cannot make
+/// the browser attach one. Basic credentials are not, since browsers replay
+/// cached ones on their own. JSON requests without a session cookie are
+/// exempt too, having no ambient authority to abuse; JSON sent with a session
+/// cookie still needs the token.
+pub async fn csrf_protection(
+    State(secret): State<HmacSecret>,
+    cookies: Cookies,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Result<Response, CsrfError> {
+    let key = Key::from(secret.0.expose_secret().as_bytes());
+    let signed_cookies = cookies.signed(&key);
+
+    let token = match signed_cookies.get(CSRF_COOKIE) {
+        Some(cookie) => CsrfToken(cookie.value().to_string()),
+        None => {
+            let token = generate_csrf_token();
+            signed_cookies.add(
+                Cookie::build(CSRF_COOKIE, token.clone())
+                    .path("/")
+                    .http_only(true)
+                    .same_site(SameSite::Strict)
+                    .finish(),
+            );
+            CsrfToken(token)
+        }
+    };
+
+    let has_session = cookies.get(SESSION_COOKIE).is_some();
+    let mut request = if requires_csrf_token(request.method(), request.headers(), has_session) {
+        let (submitted, request) = submitted_token(request).await?;
+        match submitted {
+            None => {
+                tracing::warn!(uri = %request.uri(), "Rejected request without a CSRF token");
+                return Err(CsrfError::MissingToken);
+            }
+            Some(submitted) if !constant_time_eq(submitted.as_bytes(), token.0.as_bytes()) => {
+                tracing::warn!(uri = %request.uri(), "Rejected request with an invalid CSRF token");
+                return Err(CsrfError::InvalidToken);
+            }
+            Some(_) => request,
+        }
+    } else {
+        request
+    };
+
+    request.extensions_mut().insert(token);
+    Ok(next.run(request).await)
+}
+
+fn requires_csrf_token(method: &Method, headers: &HeaderMap, has_session: bool) -> bool {
+    let safe = matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    );
+    let sessionless_json = !has_session && has_content_type(headers, "application/json");
+    !safe && !has_bearer_token(headers) && !sessionless_json
+}
+
+fn has_bearer_token(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split_once(' '))
+        .map(|(scheme, _)| scheme.eq_ignore_ascii_case("Bearer"))
+        .unwrap_or(false)
+}
+
+fn has_content_type(headers: &HeaderMap, expected: &str) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|mime| mime.trim().eq_ignore_ascii_case(expected))
+        .unwrap_or(false)
+}
+
+/// Prefers the header; otherwise buffers a form body of at most
+/// `MAX_FORM_BODY_BYTES` to read the field and hands back a request with the
+/// body restored for the handler.
+async fn submitted_token(
+    request: Request<Body>,
+) -> Result<(Option<String>, Request<Body>), CsrfError> {
+    if let Some(token) = request
+        .headers()
+        .get(CSRF_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        return Ok((Some(token.to_string()), request));
+    }
+
+    if !has_content_type(request.headers(), "application/x-www-form-urlencoded") {
+        return Ok((None, request));
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = hyper::body::to_bytes(Limited::new(body, MAX_FORM_BODY_BYTES))
+        .await
+        .map_err(|e| match e.downcast_ref::<LengthLimitError>() {
+            Some(_) => CsrfError::BodyTooLarge,
+            None => CsrfError::UnexpectedError(eyre!(e).wrap_err("Failed to read the form body.")),
+        })?;
+    let token = form_field(&bytes, CSRF_FIELD);
+    Ok((token, Request::from_parts(parts, Body::from(bytes))))
+}
+
+fn form_field(body: &[u8], name: &str) -> Option<String> {
+    let body = std::str::from_utf8(body).ok()?;
+    body.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| {
+            urlencoding::decode(&value.replace('+', " "))
+                .ok()
+                .map(|value| value.into_owned())
+        })
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+// endregion: -- CSRF Middleware
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_token_is_read_from_a_form_body() {
+        let body = b"username=ursula&csrf_token=abc%2B123&password=secret";
+        assert_eq!(form_field(body, CSRF_FIELD).as_deref(), Some("abc+123"));
+        assert_eq!(form_field(b"username=ursula", CSRF_FIELD), None);
+    }
+
+    #[test]
+    fn only_unsafe_requests_that_could_ride_a_session_need_a_token() {
+        let mut json = HeaderMap::new();
+        json.insert(
+            header::CONTENT_TYPE,
+            "application/json; charset=utf-8".parse().unwrap(),
+        );
+        let mut form = HeaderMap::new();
+        form.insert(
+            header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded".parse().unwrap(),
+        );
+        let mut bearer_json = json.clone();
+        bearer_json.insert(header::AUTHORIZATION, "Bearer z2a_abc".parse().unwrap());
+        let mut basic_form = form.clone();
+        basic_form.insert(
+            header::AUTHORIZATION,
+            "Basic dXJzdWxhOmxlZ3Vpbg==".parse().unwrap(),
+        );
+
+        assert!(!requires_csrf_token(&Method::GET, &form, true));
+        assert!(!requires_csrf_token(&Method::POST, &json, false));
+        assert!(!requires_csrf_token(&Method::POST, &bearer_json, true));
+        assert!(requires_csrf_token(&Method::POST, &json, true));
+        assert!(requires_csrf_token(&Method::POST, &form, false));
+        assert!(requires_csrf_token(&Method::POST, &basic_form, true));
+        assert!(requires_csrf_token(
+            &Method::DELETE,
+            &HeaderMap::new(),
+            true
+        ));
+    }
+}
@@ -7,7 +7,7 @@ use surrealdb::{
 };
 use surrealdb_migrations::SurrealdbMigrations;
 
-use crate::configuration::Settings;
+use crate::{configuration::Settings, prometheus::time_query};
 
 // region: -- Database
 #[derive(Clone, Debug)]
@@ -78,7 +78,9 @@ impl<'c> Transaction<'c> {
     pub fn begin(conn: &'c Surreal<Client>) -> BoxFuture<'c, Result<Self, surrealdb::Error>> {
         Box::pin(async move {
             let sql = "BEGIN TRANSACTION;".to_string();
-            conn.query(sql).await?.check()?;
+            time_query("transaction.begin", conn.query(sql))
+                .await?
+                .check()?;
 
             Ok(Self { conn, open: true })
         })
@@ -86,7 +88,9 @@ impl<'c> Transaction<'c> {
 
     pub async fn commit(mut self) -> std::result::Result<Response, surrealdb::Error> {
         let sql = "COMMIT TRANSACTION;";
-        let response = self.conn.query(sql).await?.check()?;
+        let response = time_query("transaction.commit", self.conn.query(sql))
+            .await?
+            .check()?;
         self.open = false;
         Ok(response)
     }
@@ -2,6 +2,6 @@ mod new_subscriber;
 mod subscriber_email;
 mod subscriber_name;
 
-pub use new_subscriber::NewSubscriber;
-pub use subscriber_email::SubscriberEmail;
-pub use subscriber_name::SubscriberName;
+pub use new_subscriber::{FieldError, NewSubscriber};
+pub use subscriber_email::{SubscriberEmail, SubscriberEmailError};
+pub use subscriber_name::{SubscriberName, SubscriberNameError};
@@ -1,8 +1,52 @@
 use crate::domain::{SubscriberEmail, SubscriberName};
 use serde::Serialize;
+use utoipa::ToSchema;
 
 #[derive(Serialize, Debug, Clone)]
 pub struct NewSubscriber {
     pub email: SubscriberEmail,
     pub name: SubscriberName,
 }
+
+/// Why one input field was rejected. `code` is stable, `message` is for people.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl NewSubscriber {
+    /// Validates every field, reporting all of the problems at once.
+    pub fn parse(name: String, email: String) -> Result<Self, Vec<FieldError>> {
+        let name = SubscriberName::parse(name).map_err(|e| FieldError {
+            field: "name",
+            code: e.code(),
+            message: e.to_string(),
+        });
+        let email = SubscriberEmail::parse(email).map_err(|e| FieldError {
+            field: "email",
+            code: e.code(),
+            message: e.to_string(),
+        });
+
+        match (name, email) {
+            (Ok(name), Ok(email)) => Ok(Self { email, name }),
+            (name, email) => Err([name.err(), email.err()].into_iter().flatten().collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NewSubscriber;
+    use claims::assert_err;
+
+    #[test]
+    fn all_invalid_fields_are_reported() {
+        let errors = assert_err!(NewSubscriber::parse("".into(), "not-an-email".into()));
+
+        let fields: Vec<_> = errors.iter().map(|e| (e.field, e.code)).collect();
+        assert_eq!(fields, [("name", "empty"), ("email", "invalid_syntax")]);
+    }
+}
@@ -4,16 +4,45 @@ use std::fmt::{Display, Formatter};
 use serde::{Deserialize, Serialize};
 use validator::validate_email;
 
+/// The longest address SMTP can deliver to (RFC 5321).
+const MAX_LENGTH: usize = 254;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SubscriberEmail(String);
 
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum SubscriberEmailError {
+    #[error("Please enter your email address.")]
+    Empty,
+    #[error("The email address must be at most 254 characters long.")]
+    TooLong,
+    #[error("This does not look like an email address.")]
+    InvalidSyntax,
+}
+
+impl SubscriberEmailError {
+    /// Stable, machine-readable code for API clients.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::TooLong => "too_long",
+            Self::InvalidSyntax => "invalid_syntax",
+        }
+    }
+}
+
 impl SubscriberEmail {
-    pub fn parse(s: String) -> Result<SubscriberEmail, String> {
-        if validate_email(&s) {
-            Ok(Self(s))
-        } else {
-            Err(format!("{} is not a valid email address.", s))
+    pub fn parse(s: String) -> Result<SubscriberEmail, SubscriberEmailError> {
+        if s.trim().is_empty() {
+            return Err(SubscriberEmailError::Empty);
         }
+        if s.len() > MAX_LENGTH {
+            return Err(SubscriberEmailError::TooLong);
+        }
+        if !validate_email(&s) {
+            return Err(SubscriberEmailError::InvalidSyntax);
+        }
+        Ok(Self(s))
     }
 }
 
@@ -31,8 +60,8 @@ impl Display for SubscriberEmail {
 
 #[cfg(test)]
 mod tests {
-    use super::SubscriberEmail;
-    use claims::assert_err;
+    use super::{SubscriberEmail, SubscriberEmailError};
+    use claims::{assert_err, assert_err_eq};
     use fake::faker::internet::en::SafeEmail;
     use fake::Fake;
     use rand::{rngs::StdRng, SeedableRng};
@@ -57,13 +86,16 @@ mod tests {
     #[test]
     fn empty_string_is_rejected() {
         let email = "".to_string();
-        assert_err!(SubscriberEmail::parse(email));
+        assert_err_eq!(SubscriberEmail::parse(email), SubscriberEmailError::Empty);
     }
 
     #[test]
     fn email_missing_at_symbol_is_rejected() {
         let email = "ursula_le_guin.gmail.com".to_string();
-        assert_err!(SubscriberEmail::parse(email));
+        assert_err_eq!(
+            SubscriberEmail::parse(email),
+            SubscriberEmailError::InvalidSyntax
+        );
     }
 
     #[test]
@@ -71,4 +103,10 @@ mod tests {
         let email = "@gmail.com".to_string();
         assert_err!(SubscriberEmail::parse(email));
     }
+
+    #[test]
+    fn an_overly_long_email_is_rejected() {
+        let email = format!("{}@gmail.com", "a".repeat(250));
+        assert_err_eq!(SubscriberEmail::parse(email), SubscriberEmailError::TooLong);
+    }
 }
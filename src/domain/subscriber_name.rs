@@ -1,21 +1,45 @@
 use serde::Serialize;
 use unicode_segmentation::UnicodeSegmentation;
 
+const MAX_LENGTH: usize = 256;
+const FORBIDDEN_CHARACTERS: [char; 9] = ['/', '(', ')', '"', '<', '>', '\\', '{', '}'];
+
 #[derive(Serialize, Debug, Clone)]
 pub struct SubscriberName(String);
 
-impl SubscriberName {
-    pub fn parse(s: String) -> Result<SubscriberName, String> {
-        let is_empty_or_whitespace = s.trim().is_empty();
-        let is_too_long = s.graphemes(true).count() > 256;
-        let forbidden_chars = ['/', '(', ')', '"', '<', '>', '\\', '{', '}'];
-        let contains_forbidden_chars = s.chars().any(|char| forbidden_chars.contains(&char));
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum SubscriberNameError {
+    #[error("Please enter your name.")]
+    Empty,
+    #[error("The name must be at most 256 characters long.")]
+    TooLong,
+    #[error("The name must not contain `{0}`.")]
+    ForbiddenCharacter(char),
+}
+
+impl SubscriberNameError {
+    /// Stable, machine-readable code for API clients.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::TooLong => "too_long",
+            Self::ForbiddenCharacter(_) => "forbidden_character",
+        }
+    }
+}
 
-        if is_empty_or_whitespace || is_too_long || contains_forbidden_chars {
-            Err(format!("{} is not a valid subscriber name.", s))
-        } else {
-            Ok(Self(s))
+impl SubscriberName {
+    pub fn parse(s: String) -> Result<SubscriberName, SubscriberNameError> {
+        if s.trim().is_empty() {
+            return Err(SubscriberNameError::Empty);
+        }
+        if s.graphemes(true).count() > MAX_LENGTH {
+            return Err(SubscriberNameError::TooLong);
         }
+        if let Some(c) = s.chars().find(|c| FORBIDDEN_CHARACTERS.contains(c)) {
+            return Err(SubscriberNameError::ForbiddenCharacter(c));
+        }
+        Ok(Self(s))
     }
 }
 
@@ -27,8 +51,8 @@ impl AsRef<str> for SubscriberName {
 
 #[cfg(test)]
 mod tests {
-    use crate::domain::SubscriberName;
-    use claims::{assert_err, assert_ok};
+    use crate::domain::{SubscriberName, SubscriberNameError};
+    use claims::{assert_err, assert_err_eq, assert_ok};
 
     #[test]
     fn a_256_grapheme_long_name_is_valid() {
@@ -39,13 +63,13 @@ mod tests {
     #[test]
     fn a_name_longer_than_256_graphemes_is_rejected() {
         let name = "a".repeat(257);
-        assert_err!(SubscriberName::parse(name));
+        assert_err_eq!(SubscriberName::parse(name), SubscriberNameError::TooLong);
     }
 
     #[test]
     fn whitespace_only_names_are_rejected() {
         let name = " ".to_string();
-        assert_err!(SubscriberName::parse(name));
+        assert_err_eq!(SubscriberName::parse(name), SubscriberNameError::Empty);
     }
 
     #[test]
@@ -56,9 +80,12 @@ mod tests {
 
     #[test]
     fn names_containing_invalid_chars_are_rejected() {
-        for name in &['/', '(', ')', '"', '<', '>', '\\', '{', '}'] {
-            let name = name.to_string();
-            assert_err!(SubscriberName::parse(name));
+        for c in ['/', '(', ')', '"', '<', '>', '\\', '{', '}'] {
+            let name = format!("Ursula {}", c);
+            assert_err_eq!(
+                SubscriberName::parse(name),
+                SubscriberNameError::ForbiddenCharacter(c)
+            );
         }
     }
 
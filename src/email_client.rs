@@ -1,7 +1,12 @@
 use reqwest::Client;
 use secrecy::{ExposeSecret, Secret};
 
-use crate::domain::SubscriberEmail;
+use crate::{
+    domain::SubscriberEmail,
+    prometheus::{EMAILS_FAILED_TOTAL, EMAILS_SENT_TOTAL},
+    request_id::{RequestId, REQUEST_ID_HEADER},
+    telemetry,
+};
 
 #[derive(Debug, Clone)]
 pub struct EmailClient {
@@ -12,6 +17,9 @@ pub struct EmailClient {
 }
 
 impl EmailClient {
+    /// Labels this client's deliveries in the email metrics.
+    const TRANSPORT: &'static str = "postmark";
+
     pub fn new(
         base_url: String,
         sender: SubscriberEmail,
@@ -27,6 +35,17 @@ impl EmailClient {
         }
     }
 
+    /// Whether the provider answers at all. Any response short of a server
+    /// error counts: the probe is unauthenticated and sends nothing.
+    #[tracing::instrument(name = "Probing the email provider", skip(self))]
+    pub async fn probe(&self) -> Result<(), reqwest::Error> {
+        let response = self.http_client.head(&self.base_url).send().await?;
+        if response.status().is_server_error() {
+            response.error_for_status()?;
+        }
+        Ok(())
+    }
+
     #[tracing::instrument(
         name = "Sending email",
         skip(self, recipient, subject, html_content, text_content),
@@ -53,19 +72,32 @@ impl EmailClient {
             text_body: text_content.to_owned(),
         };
 
-        let _builder = self
-            .http_client
-            .post(&url)
-            .header(
-                "X-Postmark-Server-Token",
-                self.authorization_token.expose_secret(),
-            )
+        let mut builder = self.http_client.post(&url).header(
+            "X-Postmark-Server-Token",
+            self.authorization_token.expose_secret(),
+        );
+        // Lets the provider's logs be matched to the request that sent the email
+        if let Some(request_id) = RequestId::current() {
+            builder = builder.header(REQUEST_ID_HEADER, request_id.as_str());
+        }
+        // Continues this trace in the provider's spans, if it reads `traceparent`
+        builder = builder.headers(telemetry::trace_context_headers());
+
+        let outcome = builder
             .json(&request_body)
             .send()
-            .await?
-            .error_for_status()?;
-
-        Ok(())
+            .await
+            .and_then(|response| response.error_for_status());
+        match outcome {
+            Ok(_) => {
+                metrics::increment_counter!(EMAILS_SENT_TOTAL, "transport" => Self::TRANSPORT);
+                Ok(())
+            }
+            Err(e) => {
+                metrics::increment_counter!(EMAILS_FAILED_TOTAL, "transport" => Self::TRANSPORT);
+                Err(e)
+            }
+        }
     }
 }
 
@@ -1,5 +1,191 @@
-use axum::response::{IntoResponse, Response};
+use axum::{
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+};
 use hyper::StatusCode;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    authentication::Role, domain::FieldError, problem::Problem, telemetry::report_unexpected_error,
+};
+
+// region: -- AppError
+/// Machine-readable error codes sent as the `code` of every problem body.
+/// They are part of the API: rename one and clients break.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadRequest,
+    ValidationFailed,
+    AuthenticationFailed,
+    UnknownSubscriptionToken,
+    Forbidden,
+    CsrfTokenMissing,
+    CsrfTokenInvalid,
+    NotFound,
+    UnknownSubscriber,
+    UnknownUser,
+    UnknownApiToken,
+    MethodNotAllowed,
+    UsernameTaken,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    UnprocessableEntity,
+    TooManyRequests,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            Self::BadRequest | Self::ValidationFailed => StatusCode::BAD_REQUEST,
+            Self::AuthenticationFailed | Self::UnknownSubscriptionToken => StatusCode::UNAUTHORIZED,
+            Self::Forbidden | Self::CsrfTokenMissing | Self::CsrfTokenInvalid => {
+                StatusCode::FORBIDDEN
+            }
+            Self::NotFound
+            | Self::UnknownSubscriber
+            | Self::UnknownUser
+            | Self::UnknownApiToken => StatusCode::NOT_FOUND,
+            Self::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            Self::UsernameTaken => StatusCode::CONFLICT,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::UnprocessableEntity => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            Self::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::BadRequest => "Bad request",
+            Self::ValidationFailed => "Invalid input",
+            Self::AuthenticationFailed => "Authentication failed",
+            Self::UnknownSubscriptionToken => "Unknown subscription token",
+            Self::Forbidden => "Forbidden",
+            Self::CsrfTokenMissing => "Missing CSRF token",
+            Self::CsrfTokenInvalid => "Invalid CSRF token",
+            Self::NotFound => "Not found",
+            Self::UnknownSubscriber => "Unknown subscriber",
+            Self::UnknownUser => "Unknown user",
+            Self::UnknownApiToken => "Unknown API token",
+            Self::MethodNotAllowed => "Method not allowed",
+            Self::UsernameTaken => "Username taken",
+            Self::PayloadTooLarge => "Request body too large",
+            Self::UnsupportedMediaType => "Unsupported media type",
+            Self::UnprocessableEntity => "Unprocessable request body",
+            Self::TooManyRequests => "Too many requests",
+            Self::InternalError => "Something went wrong",
+        }
+    }
+
+    /// The generic code for responses that did not come from an `AppError`.
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => Self::AuthenticationFailed,
+            StatusCode::FORBIDDEN => Self::Forbidden,
+            StatusCode::NOT_FOUND => Self::NotFound,
+            StatusCode::METHOD_NOT_ALLOWED => Self::MethodNotAllowed,
+            StatusCode::PAYLOAD_TOO_LARGE => Self::PayloadTooLarge,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => Self::UnsupportedMediaType,
+            StatusCode::UNPROCESSABLE_ENTITY => Self::UnprocessableEntity,
+            StatusCode::TOO_MANY_REQUESTS => Self::TooManyRequests,
+            status if status.is_client_error() => Self::BadRequest,
+            _ => Self::InternalError,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let code = serde_json::to_value(self).map_err(|_| std::fmt::Error)?;
+        f.write_str(code.as_str().unwrap_or_default())
+    }
+}
+
+/// What every handler error becomes on the way out: an `application/problem+json`
+/// body (or an HTML page, see `problem::render_problems`). The `source` is
+/// reported and logged, never sent.
+pub struct AppError {
+    code: ErrorCode,
+    detail: Option<String>,
+    errors: Vec<FieldError>,
+    challenge: Option<&'static str>,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode) -> Self {
+        Self {
+            code,
+            detail: None,
+            errors: Vec::new(),
+            challenge: None,
+            source: None,
+        }
+    }
+
+    /// An internal failure; `error` keeps its cause chain for Sentry and logs.
+    pub fn unexpected(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self {
+            source: Some(Box::new(error)),
+            ..Self::new(ErrorCode::InternalError)
+        }
+    }
+
+    /// A human-readable explanation. Must be safe to show to the client.
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Every rejected input field, listed under `errors`.
+    pub fn field_errors(mut self, errors: Vec<FieldError>) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Sent as `WWW-Authenticate`.
+    pub fn challenge(mut self, challenge: &'static str) -> Self {
+        self.challenge = Some(challenge);
+        self
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+}
+
+impl std::fmt::Debug for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.source {
+            Some(source) => write!(f, "{}: {:?}", self.code, source),
+            None => write!(f, "{}", self.code),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        if let Some(source) = &self.source {
+            tracing::error!(cause_chain = ?source, "Unexpected error");
+            report_unexpected_error(source.as_ref());
+        }
+        let mut problem = Problem::new(self.code, self.detail);
+        problem.errors = self.errors;
+        let mut response = problem.into_response();
+        if let Some(challenge) = self.challenge {
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static(challenge),
+            );
+        }
+        response
+    }
+}
+// endregion: AppError
 
 // region: -- LoginError
 #[derive(thiserror::Error)]
@@ -16,12 +202,18 @@ impl std::fmt::Debug for LoginError {
     }
 }
 
+impl From<LoginError> for AppError {
+    fn from(error: LoginError) -> Self {
+        match error {
+            LoginError::AuthError(_) => AppError::new(ErrorCode::AuthenticationFailed),
+            LoginError::UnexpectedError(_) => AppError::unexpected(error),
+        }
+    }
+}
+
 impl IntoResponse for LoginError {
     fn into_response(self) -> Response {
-        match self {
-            LoginError::AuthError(_) => StatusCode::UNAUTHORIZED.into_response(),
-            LoginError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-        }
+        AppError::from(self).into_response()
     }
 }
 // endregion: LoginError
@@ -39,8 +231,8 @@ pub enum AuthError {
 // region: -- SubscribeError
 #[derive(thiserror::Error)]
 pub enum SubscribeError {
-    #[error("{0}")]
-    ValidationError(String),
+    #[error("The subscriber details are invalid.")]
+    ValidationError(Vec<FieldError>),
     #[error(transparent)]
     UnexpectedError(#[from] color_eyre::eyre::Error),
 }
@@ -57,12 +249,20 @@ impl std::fmt::Debug for SubscribeError {
     }
 }
 
+impl From<SubscribeError> for AppError {
+    fn from(error: SubscribeError) -> Self {
+        match error {
+            SubscribeError::ValidationError(errors) => {
+                AppError::new(ErrorCode::ValidationFailed).field_errors(errors)
+            }
+            SubscribeError::UnexpectedError(_) => AppError::unexpected(error),
+        }
+    }
+}
+
 impl IntoResponse for SubscribeError {
     fn into_response(self) -> Response {
-        match self {
-            SubscribeError::ValidationError(_) => StatusCode::BAD_REQUEST.into_response(),
-            SubscribeError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-        }
+        AppError::from(self).into_response()
     }
 }
 // endregion: SubscribeError
@@ -88,16 +288,22 @@ impl From<surrealdb::Error> for ConfirmationError {
     }
 }
 
-impl IntoResponse for ConfirmationError {
-    fn into_response(self) -> Response {
-        match self {
-            ConfirmationError::UnknownToken => StatusCode::UNAUTHORIZED.into_response(),
-            ConfirmationError::UnexpectedError(_) => {
-                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+impl From<ConfirmationError> for AppError {
+    fn from(error: ConfirmationError) -> Self {
+        match error {
+            ConfirmationError::UnknownToken => {
+                AppError::new(ErrorCode::UnknownSubscriptionToken).detail(error.to_string())
             }
+            ConfirmationError::UnexpectedError(_) => AppError::unexpected(error),
         }
     }
 }
+
+impl IntoResponse for ConfirmationError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
 // endregion: ConfirmationError
 
 // region: -- TransactionError
@@ -114,7 +320,7 @@ impl std::fmt::Display for TransactionError {
 
 impl IntoResponse for TransactionError {
     fn into_response(self) -> Response {
-        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        AppError::unexpected(self).into_response()
     }
 }
 
@@ -173,6 +379,10 @@ impl std::fmt::Display for StoreTokenError {
 pub enum PublishError {
     #[error("Authentication failed.")]
     AuthError(#[source] color_eyre::eyre::Error),
+    #[error("Accounts with two-factor authentication must publish with an API token.")]
+    SecondFactorRequired,
+    #[error(transparent)]
+    Forbidden(#[from] AuthorizationError),
     #[error(transparent)]
     UnexpectedError(#[from] color_eyre::eyre::Error),
 }
@@ -189,23 +399,233 @@ impl std::fmt::Debug for PublishError {
     }
 }
 
-impl IntoResponse for PublishError {
-    fn into_response(self) -> Response {
-        match self {
-            PublishError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+impl From<PublishError> for AppError {
+    fn from(error: PublishError) -> Self {
+        match error {
             PublishError::AuthError(_) => {
-                let response = Response::builder()
-                    .status(StatusCode::UNAUTHORIZED)
-                    .header("WWW-Authenticate", r#"Basic realm="publish""#)
-                    .body(hyper::Body::empty())
-                    .unwrap();
-                response.into_response()
+                AppError::new(ErrorCode::AuthenticationFailed).challenge(r#"Basic realm="publish""#)
             }
+            PublishError::SecondFactorRequired => AppError::new(ErrorCode::AuthenticationFailed)
+                .detail(error.to_string())
+                .challenge(r#"Bearer realm="publish""#),
+            PublishError::Forbidden(e) => e.into(),
+            PublishError::UnexpectedError(_) => AppError::unexpected(error),
         }
     }
 }
+
+impl IntoResponse for PublishError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
 // endregion: Publish Error
 
+// region: -- AuthorizationError
+#[derive(thiserror::Error)]
+pub enum AuthorizationError {
+    #[error("This action requires the {required} role, but you are a {actual}.")]
+    Forbidden { required: Role, actual: Role },
+    #[error(transparent)]
+    UnexpectedError(#[from] color_eyre::eyre::Error),
+}
+
+impl std::fmt::Debug for AuthorizationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl From<AuthorizationError> for AppError {
+    fn from(error: AuthorizationError) -> Self {
+        match error {
+            AuthorizationError::Forbidden { .. } => {
+                AppError::new(ErrorCode::Forbidden).detail(error.to_string())
+            }
+            AuthorizationError::UnexpectedError(_) => AppError::unexpected(error),
+        }
+    }
+}
+
+impl IntoResponse for AuthorizationError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
+// endregion: AuthorizationError
+
+// region: -- UserError
+#[derive(thiserror::Error)]
+pub enum UserError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("The username is already taken.")]
+    UsernameTaken,
+    #[error("There is no user with the provided username.")]
+    UnknownUser,
+    #[error(transparent)]
+    UnexpectedError(#[from] color_eyre::eyre::Error),
+}
+
+impl From<surrealdb::Error> for UserError {
+    fn from(error: surrealdb::Error) -> Self {
+        Self::UnexpectedError(error.into())
+    }
+}
+
+impl std::fmt::Debug for UserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl From<UserError> for AppError {
+    fn from(error: UserError) -> Self {
+        let code = match error {
+            UserError::ValidationError(_) => ErrorCode::ValidationFailed,
+            UserError::UsernameTaken => ErrorCode::UsernameTaken,
+            UserError::UnknownUser => ErrorCode::UnknownUser,
+            UserError::UnexpectedError(_) => return AppError::unexpected(error),
+        };
+        AppError::new(code).detail(error.to_string())
+    }
+}
+
+impl IntoResponse for UserError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
+// endregion: UserError
+
+// region: -- AdminError
+#[derive(thiserror::Error)]
+pub enum AdminError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("There is no subscriber with the provided email.")]
+    UnknownSubscriber,
+    #[error(transparent)]
+    UnexpectedError(#[from] color_eyre::eyre::Error),
+}
+
+impl From<surrealdb::Error> for AdminError {
+    fn from(error: surrealdb::Error) -> Self {
+        Self::UnexpectedError(error.into())
+    }
+}
+
+impl std::fmt::Debug for AdminError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl From<AdminError> for AppError {
+    fn from(error: AdminError) -> Self {
+        let code = match error {
+            AdminError::ValidationError(_) => ErrorCode::ValidationFailed,
+            AdminError::UnknownSubscriber => ErrorCode::UnknownSubscriber,
+            AdminError::UnexpectedError(_) => return AppError::unexpected(error),
+        };
+        AppError::new(code).detail(error.to_string())
+    }
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
+// endregion: AdminError
+
+// region: -- ApiTokenError
+#[derive(thiserror::Error)]
+pub enum ApiTokenError {
+    #[error("Authentication failed.")]
+    AuthError(#[source] color_eyre::eyre::Error),
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("There is no API token with the provided id.")]
+    UnknownToken,
+    #[error(transparent)]
+    UnexpectedError(#[from] color_eyre::eyre::Error),
+}
+
+impl From<surrealdb::Error> for ApiTokenError {
+    fn from(error: surrealdb::Error) -> Self {
+        Self::UnexpectedError(error.into())
+    }
+}
+
+impl std::fmt::Debug for ApiTokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl From<ApiTokenError> for AppError {
+    fn from(error: ApiTokenError) -> Self {
+        match error {
+            ApiTokenError::AuthError(_) => {
+                AppError::new(ErrorCode::AuthenticationFailed).challenge("Bearer")
+            }
+            ApiTokenError::ValidationError(_) => {
+                AppError::new(ErrorCode::ValidationFailed).detail(error.to_string())
+            }
+            ApiTokenError::UnknownToken => {
+                AppError::new(ErrorCode::UnknownApiToken).detail(error.to_string())
+            }
+            ApiTokenError::UnexpectedError(_) => AppError::unexpected(error),
+        }
+    }
+}
+
+impl IntoResponse for ApiTokenError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
+// endregion: ApiTokenError
+
+// region: -- CsrfError
+#[derive(thiserror::Error)]
+pub enum CsrfError {
+    #[error("The request is missing a CSRF token.")]
+    MissingToken,
+    #[error("The CSRF token does not match.")]
+    InvalidToken,
+    #[error("The form body is too large.")]
+    BodyTooLarge,
+    #[error(transparent)]
+    UnexpectedError(#[from] color_eyre::eyre::Error),
+}
+
+impl std::fmt::Debug for CsrfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl From<CsrfError> for AppError {
+    fn from(error: CsrfError) -> Self {
+        let code = match error {
+            CsrfError::MissingToken => ErrorCode::CsrfTokenMissing,
+            CsrfError::InvalidToken => ErrorCode::CsrfTokenInvalid,
+            CsrfError::BodyTooLarge => ErrorCode::PayloadTooLarge,
+            CsrfError::UnexpectedError(_) => return AppError::unexpected(error),
+        };
+        AppError::new(code).detail(error.to_string())
+    }
+}
+
+impl IntoResponse for CsrfError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
+// endregion: CsrfError
+
 // region: -- Error Chaining (clever)
 pub fn error_chain_fmt(
     e: &impl std::error::Error,
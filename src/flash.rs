@@ -0,0 +1,176 @@
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use tower_cookies::{cookie::SameSite, Cookie, Cookies, Key};
+
+use crate::startup::HmacSecret;
+
+const FLASH_COOKIE: &str = "_flash";
+
+// region: -- Flash Message
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// Doubles as the CSS class of the rendered message.
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Info => write!(f, "info"),
+            Level::Success => write!(f, "success"),
+            Level::Warning => write!(f, "warning"),
+            Level::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Plain text: templates escape it like any other value, so it is safe to
+/// include user input.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FlashMessage {
+    pub level: Level,
+    pub message: String,
+}
+// endregion: -- Flash Message
+
+// region: -- Flash Extractor
+/// Queued one-shot messages for post-redirect-get flows, kept in a private
+/// cookie until a page renders them with [`Flash::take`].
+pub struct Flash {
+    cookies: Cookies,
+    key: Key,
+    queued: Vec<FlashMessage>,
+}
+
+impl Flash {
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(Level::Info, message);
+    }
+
+    pub fn success(&mut self, message: impl Into<String>) {
+        self.push(Level::Success, message);
+    }
+
+    pub fn warning(&mut self, message: impl Into<String>) {
+        self.push(Level::Warning, message);
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(Level::Error, message);
+    }
+
+    pub fn push(&mut self, level: Level, message: impl Into<String>) {
+        self.queued.push(FlashMessage {
+            level,
+            message: message.into(),
+        });
+        self.save();
+    }
+
+    /// Every queued message, oldest first. They are shown once: the cookie is
+    /// cleared unless more messages are pushed afterwards.
+    pub fn take(&mut self) -> Vec<FlashMessage> {
+        let messages = std::mem::take(&mut self.queued);
+        self.save();
+        messages
+    }
+
+    fn save(&self) {
+        let private_cookies = self.cookies.private(&self.key);
+        if self.queued.is_empty() {
+            private_cookies.remove(Cookie::build(FLASH_COOKIE, "").path("/").finish());
+            return;
+        }
+
+        match serde_json::to_string(&self.queued) {
+            Ok(value) => private_cookies.add(
+                Cookie::build(FLASH_COOKIE, value)
+                    .path("/")
+                    .http_only(true)
+                    .same_site(SameSite::Lax)
+                    .finish(),
+            ),
+            Err(e) => tracing::error!(error.cause_chain = ?e, "Failed to store flash messages"),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Flash
+where
+    S: Send + Sync,
+    HmacSecret: FromRef<S>,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let cookies = Cookies::from_request_parts(parts, state)
+            .await
+            .map_err(|e| e.into_response())?;
+
+        let HmacSecret(secret) = HmacSecret::from_ref(state);
+        let key = Key::from(secret.expose_secret().as_bytes());
+
+        // A cookie we cannot decrypt or parse is dropped rather than rejected
+        let queued = cookies
+            .private(&key)
+            .get(FLASH_COOKIE)
+            .and_then(|cookie| serde_json::from_str(cookie.value()).ok())
+            .unwrap_or_default();
+
+        Ok(Self {
+            cookies,
+            key,
+            queued,
+        })
+    }
+}
+// endregion: -- Flash Extractor
+
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+
+    use super::{FlashMessage, Level};
+
+    #[derive(Template)]
+    #[template(
+        source = r#"{% for m in flash %}<p class="{{ m.level }}">{{ m.message }}</p>{% endfor %}"#,
+        ext = "html"
+    )]
+    struct FlashTemplate {
+        flash: Vec<FlashMessage>,
+    }
+
+    #[test]
+    fn messages_are_html_escaped() {
+        let html = FlashTemplate {
+            flash: vec![
+                FlashMessage {
+                    level: Level::Error,
+                    message: "<script>alert('x')</script>".into(),
+                },
+                FlashMessage {
+                    level: Level::Success,
+                    message: "Saved.".into(),
+                },
+            ],
+        }
+        .render()
+        .unwrap();
+
+        assert!(!html.contains("<script>"));
+        assert!(html.contains(r#"<p class="error">&lt;script&gt;"#));
+        assert!(html.contains(r#"<p class="success">Saved.</p>"#));
+    }
+}
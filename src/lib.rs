@@ -1,9 +1,21 @@
+pub mod audit;
 pub mod authentication;
+pub mod cli;
 pub mod configuration;
+pub mod csrf;
 pub mod db;
 pub mod domain;
 pub mod email_client;
 pub mod error;
+pub mod flash;
+pub mod log_filter;
+pub mod openapi;
+pub mod problem;
+pub mod prometheus;
+pub mod redaction;
+pub mod request_id;
 pub mod routes;
+pub mod security_headers;
+pub mod session;
 pub mod startup;
 pub mod telemetry;
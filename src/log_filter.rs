@@ -0,0 +1,137 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+pub type FilterHandle = reload::Handle<EnvFilter, Registry>;
+
+static LOG_FILTER: OnceCell<LogFilter> = OnceCell::new();
+
+// region: -- Log Filter
+/// The process-wide `EnvFilter`, replaceable while the server runs.
+pub struct LogFilter {
+    handle: FilterHandle,
+    default_directive: String,
+    state: Mutex<FilterState>,
+}
+
+struct FilterState {
+    directive: String,
+    reverts_at: Option<Instant>,
+    revert: Option<JoinHandle<()>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LogFilterStatus {
+    pub directive: String,
+    pub default_directive: String,
+    /// Seconds until the default directive is restored, if a TTL was set.
+    pub reverts_in_seconds: Option<u64>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum LogFilterError {
+    #[error("Invalid filter directive: {0}")]
+    InvalidDirective(String),
+    #[error("The TTL is too long.")]
+    InvalidTtl,
+    #[error("The log filter cannot be reloaded.")]
+    Unavailable,
+}
+
+impl LogFilter {
+    /// Registers the filter `get_subscriber` installed. Only the first
+    /// subscriber built in the process is controllable.
+    pub fn install(handle: FilterHandle, directive: String) {
+        let _ = LOG_FILTER.set(Self {
+            handle,
+            default_directive: directive.clone(),
+            state: Mutex::new(FilterState {
+                directive,
+                reverts_at: None,
+                revert: None,
+            }),
+        });
+    }
+
+    pub fn global() -> Result<&'static Self, LogFilterError> {
+        LOG_FILTER.get().ok_or(LogFilterError::Unavailable)
+    }
+
+    pub fn status(&self) -> LogFilterStatus {
+        let state = self.state.lock().unwrap();
+        LogFilterStatus {
+            directive: state.directive.clone(),
+            default_directive: self.default_directive.clone(),
+            reverts_in_seconds: state
+                .reverts_at
+                .map(|at| at.saturating_duration_since(Instant::now()).as_secs()),
+        }
+    }
+
+    /// Replaces the filter with `directive` (`RUST_LOG` syntax). With a
+    /// `ttl` the default directive comes back on its own afterwards.
+    pub fn set(
+        &'static self,
+        directive: &str,
+        ttl: Option<Duration>,
+    ) -> Result<LogFilterStatus, LogFilterError> {
+        let filter = EnvFilter::try_new(directive)
+            .map_err(|e| LogFilterError::InvalidDirective(e.to_string()))?;
+        let reverts_at = ttl
+            .map(|ttl| {
+                Instant::now()
+                    .checked_add(ttl)
+                    .ok_or(LogFilterError::InvalidTtl)
+            })
+            .transpose()?;
+        self.handle
+            .reload(filter)
+            .map_err(|_| LogFilterError::Unavailable)?;
+
+        {
+            let mut state = self.state.lock().unwrap();
+            if let Some(revert) = state.revert.take() {
+                revert.abort();
+            }
+            state.directive = directive.to_string();
+            state.reverts_at = reverts_at;
+            state.revert = ttl.map(|ttl| {
+                tokio::spawn(async move {
+                    tokio::time::sleep(ttl).await;
+                    if let Err(e) = self.restore() {
+                        tracing::error!(error = %e, "Failed to restore the default log filter");
+                    }
+                })
+            });
+        }
+
+        tracing::warn!(directive, ttl_seconds = ?ttl.map(|ttl| ttl.as_secs()), "Log filter changed");
+        Ok(self.status())
+    }
+
+    /// Goes back to the directive the process started with.
+    pub fn reset(&'static self) -> Result<LogFilterStatus, LogFilterError> {
+        self.set(&self.default_directive, None)
+    }
+
+    fn restore(&self) -> Result<(), LogFilterError> {
+        let filter = EnvFilter::try_new(&self.default_directive)
+            .map_err(|e| LogFilterError::InvalidDirective(e.to_string()))?;
+        self.handle
+            .reload(filter)
+            .map_err(|_| LogFilterError::Unavailable)?;
+
+        let mut state = self.state.lock().unwrap();
+        state.directive = self.default_directive.clone();
+        state.reverts_at = None;
+        state.revert = None;
+        Ok(())
+    }
+}
+// endregion: -- Log Filter
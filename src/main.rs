@@ -1,35 +1,55 @@
-use color_eyre::eyre::Context;
-use tracing::info;
+use clap::Parser;
 use zero2axum::{
+    cli::Cli,
     configuration::get_configuration,
-    db::Database,
-    startup::Application,
-    telemetry::{get_subscriber, init_subscriber},
+    telemetry::{get_subscriber, init_sentry, init_subscriber, init_tracer},
 };
 
 #[tokio::main]
 async fn main() -> color_eyre::Result<()> {
-    let subscriber = get_subscriber("zero2axum".into(), "info".into(), std::io::stdout);
-    init_subscriber(subscriber);
-    color_eyre::install().expect("Failed to install `color_eyre`");
-
-    // init_sentry();
-    // init_honeycomb();
+    let cli = Cli::parse();
+    // Commands report their own configuration errors; telemetry falls back to defaults
+    let configuration = get_configuration().ok();
 
-    let configuration = get_configuration().expect("Failed to read configuration.");
+    let redaction = configuration
+        .as_ref()
+        .map(|configuration| configuration.redaction.clone())
+        .unwrap_or_default();
 
-    let database = Database::new(&configuration)
-        .await
-        .context("Application failed to create SurrealDB")?;
+    // Keep stdout clean for commands that print data (e.g. `subscribers export`)
+    if cli.is_serve() {
+        let telemetry = configuration
+            .as_ref()
+            .map(|configuration| configuration.telemetry.clone())
+            .unwrap_or_default();
+        let tracer = init_tracer("zero2axum", &telemetry).expect("Failed to install the tracer.");
+        let subscriber = get_subscriber(
+            "zero2axum".into(),
+            "info".into(),
+            std::io::stdout,
+            tracer,
+            &redaction,
+        );
+        init_subscriber(subscriber);
+    } else {
+        let subscriber = get_subscriber(
+            "zero2axum".into(),
+            "warn".into(),
+            std::io::stderr,
+            None,
+            &redaction,
+        );
+        init_subscriber(subscriber);
+    }
+    color_eyre::install().expect("Failed to install `color_eyre`");
 
-    let application = Application::build(configuration.clone(), database)
-        .await
-        .expect("Application Failed to Start");
-    info!(
-        "Server listening on http://{}:{}",
-        configuration.application.host, configuration.application.port
-    );
-    application.run_until_stopped().await?;
+    // Dropping the guard flushes and disables Sentry, so it lives until `main` returns
+    let _sentry = configuration
+        .as_ref()
+        .and_then(|configuration| init_sentry(&configuration.sentry, &redaction));
 
-    Ok(())
+    let result = cli.run().await;
+    // Flush spans still waiting in the batch exporter
+    opentelemetry::global::shutdown_tracer_provider();
+    result
 }
@@ -0,0 +1,116 @@
+use axum::{response::IntoResponse, Json};
+use utoipa::{
+    openapi::security::{Http, HttpAuthScheme, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::{
+    domain::FieldError,
+    error::ErrorCode,
+    problem::Problem,
+    routes::{self, BodyData, Content, FormData, SubscribeResponse, SubscriberSummary},
+};
+
+// region: -- API Document
+/// The public HTTP API. `openapi.json` at the repository root is this
+/// document rendered; `tests/api/openapi.rs` fails when the two drift.
+#[derive(OpenApi)]
+#[openapi(
+    info(description = "Newsletter subscriptions and delivery."),
+    paths(
+        routes::handler_health_check,
+        routes::handler_subscribe,
+        routes::handler_confirm,
+        routes::publish_newsletter,
+        routes::list_subscribers,
+    ),
+    components(schemas(
+        FormData,
+        SubscribeResponse,
+        BodyData,
+        Content,
+        SubscriberSummary,
+        Problem,
+        FieldError,
+        ErrorCode,
+    )),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "subscriptions", description = "Signing up and confirming"),
+        (name = "newsletters", description = "Publishing issues"),
+        (name = "subscribers", description = "Reading the subscriber list"),
+        (name = "health", description = "Probes"),
+    )
+)]
+pub struct ApiDoc;
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "basic",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Basic)),
+            );
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+        }
+    }
+}
+
+pub async fn handler_openapi() -> impl IntoResponse {
+    Json(ApiDoc::openapi())
+}
+// endregion: -- API Document
+
+// region: -- Swagger UI
+#[cfg(feature = "docs-ui")]
+pub use docs_ui::handler_docs;
+
+#[cfg(feature = "docs-ui")]
+mod docs_ui {
+    use std::sync::Arc;
+
+    use axum::{
+        extract::Path,
+        http::{header, StatusCode},
+        response::{IntoResponse, Response},
+    };
+    use once_cell::sync::Lazy;
+    use utoipa_swagger_ui::Config;
+
+    /// Swagger UI styles itself inline, which the site-wide policy forbids.
+    const DOCS_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; img-src 'self' data:; \
+        style-src 'self' 'unsafe-inline'; script-src 'self'; object-src 'none'; \
+        frame-ancestors 'none'";
+
+    static CONFIG: Lazy<Arc<Config<'static>>> =
+        Lazy::new(|| Arc::new(Config::from("/openapi.json")));
+
+    /// The bundled Swagger UI assets, served under `/docs/`.
+    pub async fn handler_docs(tail: Option<Path<String>>) -> Response {
+        let tail = tail.map(|Path(tail)| tail).unwrap_or_default();
+        match utoipa_swagger_ui::serve(&tail, CONFIG.clone()) {
+            Ok(Some(file)) => (
+                [
+                    (header::CONTENT_TYPE, file.content_type),
+                    (
+                        header::CONTENT_SECURITY_POLICY,
+                        DOCS_CONTENT_SECURITY_POLICY.to_string(),
+                    ),
+                ],
+                file.bytes.into_owned(),
+            )
+                .into_response(),
+            Ok(None) => StatusCode::NOT_FOUND.into_response(),
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to serve Swagger UI");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+// endregion: -- Swagger UI
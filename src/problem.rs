@@ -0,0 +1,193 @@
+use askama::Template;
+use axum::{
+    body::{boxed, Body},
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{domain::FieldError, error::ErrorCode, request_id::RequestId};
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Plain-text rejection bodies longer than this are not quoted as `detail`.
+const MAX_DETAIL_LENGTH: usize = 512;
+
+// region: -- Problem Details
+/// An RFC 7807 problem details body. `code` is stable and meant for
+/// machines; `title` and `detail` are for people and may change.
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub title: &'static str,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Per-field validation failures.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+impl Problem {
+    pub fn new(code: ErrorCode, detail: Option<String>) -> Self {
+        Self {
+            type_: format!("/problems/{}", code),
+            title: code.title(),
+            status: code.status().as_u16(),
+            detail,
+            code,
+            request_id: RequestId::current().map(|id| id.to_string()),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+/// The JSON rendering. The problem is also stored as a response extension so
+/// `render_problems` can switch to HTML for browsers.
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let body = serde_json::to_string(&self).expect("Problems always serialise.");
+        let mut response = (
+            self.status(),
+            [(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON))],
+            body,
+        )
+            .into_response();
+        response.extensions_mut().insert(self);
+        response
+    }
+}
+// endregion: -- Problem Details
+
+// region: -- Problem Middleware
+#[derive(Template)]
+#[template(path = "error.html")]
+struct ErrorTemplate<'a> {
+    problem: &'a Problem,
+}
+
+/// Gives every error response a problem body: errors that bypassed `AppError`
+/// (extractor rejections, unknown routes) are converted here. Clients that
+/// prefer `text/html` get an error page instead of JSON.
+pub async fn render_problems(request: Request<Body>, next: Next<Body>) -> Response {
+    let wants_html = prefers_html(request.headers());
+    let mut response = next.run(request).await;
+
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+
+    let existing = response.extensions().get::<Problem>().cloned();
+    let problem = match existing {
+        Some(problem) => problem,
+        None if is_plain_text_or_empty(response.headers()) => {
+            let (parts, body) = response.into_parts();
+            let detail = match status.is_client_error() {
+                true => hyper::body::to_bytes(body)
+                    .await
+                    .ok()
+                    .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok())
+                    .filter(|text| !text.is_empty() && text.len() <= MAX_DETAIL_LENGTH),
+                false => None,
+            };
+            let mut problem = Problem::new(ErrorCode::from_status(status), detail);
+            problem.status = status.as_u16();
+            let mut converted = problem.clone().into_response();
+            for (name, value) in parts.headers.iter() {
+                if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+                    converted.headers_mut().append(name, value.clone());
+                }
+            }
+            response = converted;
+            problem
+        }
+        // Bodies a handler rendered on purpose, e.g. the readiness report
+        None => return response,
+    };
+
+    if wants_html {
+        match (ErrorTemplate { problem: &problem }).render() {
+            Ok(html) => {
+                *response.body_mut() = boxed(Body::from(html));
+                response.headers_mut().insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("text/html; charset=utf-8"),
+                );
+                response.headers_mut().remove(header::CONTENT_LENGTH);
+            }
+            Err(e) => tracing::error!(error = %e, "Failed to render the error page"),
+        }
+    }
+
+    response
+}
+
+fn is_plain_text_or_empty(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(true, |value| value.starts_with("text/plain"))
+}
+
+/// Whether `Accept` ranks `text/html` above every JSON media type.
+pub fn prefers_html(headers: &HeaderMap) -> bool {
+    let Some(accept) = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+
+    let (mut html, mut json) = (0.0_f32, 0.0_f32);
+    for range in accept.split(',') {
+        let mut params = range.split(';').map(str::trim);
+        let media_type = params.next().unwrap_or_default().to_ascii_lowercase();
+        let quality = params
+            .find_map(|param| param.strip_prefix("q="))
+            .and_then(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        match media_type.as_str() {
+            "text/html" => html = html.max(quality),
+            "application/json" | PROBLEM_JSON => json = json.max(quality),
+            _ => {}
+        }
+    }
+    html > json
+}
+// endregion: -- Problem Middleware
+
+#[cfg(test)]
+mod tests {
+    use super::prefers_html;
+    use axum::http::{header, HeaderMap, HeaderValue};
+
+    fn accept(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn browsers_get_html_and_api_clients_get_json() {
+        assert!(prefers_html(&accept(
+            "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"
+        )));
+        assert!(!prefers_html(&accept("application/json")));
+        assert!(!prefers_html(&accept("*/*")));
+        assert!(!prefers_html(&accept(
+            "text/html;q=0.5, application/problem+json"
+        )));
+        assert!(!prefers_html(&HeaderMap::new()));
+    }
+}
@@ -0,0 +1,128 @@
+use std::{future::IntoFuture, time::Instant};
+
+use axum::{
+    extract::MatchedPath,
+    http::{header, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use hyper::Body;
+use metrics::Label;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use once_cell::sync::Lazy;
+
+// region: -- Metric Names
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+pub const EMAILS_SENT_TOTAL: &str = "emails_sent_total";
+pub const EMAILS_FAILED_TOTAL: &str = "emails_failed_total";
+pub const SUBSCRIBER_SIGNUPS_TOTAL: &str = "subscriber_signups_total";
+pub const SUBSCRIBER_CONFIRMATIONS_TOTAL: &str = "subscriber_confirmations_total";
+pub const SURREALDB_QUERY_DURATION_SECONDS: &str = "surrealdb_query_duration_seconds";
+/// Newsletter emails fetched for delivery but not yet handed to the provider.
+pub const DELIVERY_QUEUE_DEPTH: &str = "newsletter_delivery_queue_depth";
+// endregion: -- Metric Names
+
+// region: -- Recorder
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The process-wide recorder, installed on first use. Every `Application`
+/// in the process (e.g. in tests) reports into the same registry.
+static RECORDER: Lazy<PrometheusHandle> = Lazy::new(|| {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Suffix("_duration_seconds".to_string()),
+            LATENCY_BUCKETS,
+        )
+        .expect("Latency buckets are not empty.")
+        .install_recorder()
+        .expect("Failed to install the Prometheus recorder.")
+});
+
+pub fn install_recorder() {
+    Lazy::force(&RECORDER);
+}
+
+/// Every metric in the Prometheus text exposition format.
+pub async fn handler_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        RECORDER.render(),
+    )
+}
+// endregion: -- Recorder
+
+// region: -- HTTP Metrics Middleware
+/// Counts and times every request by method, route template and status.
+/// Requests that match no route share the `unmatched` label so arbitrary
+/// paths cannot blow up the series count.
+pub async fn track_http_metrics(request: Request<Body>, next: Next<Body>) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    let labels = vec![
+        Label::new("method", method),
+        Label::new("route", route),
+        Label::new("status", response.status().as_u16().to_string()),
+    ];
+    metrics::increment_counter!(HTTP_REQUESTS_TOTAL, labels.clone());
+    metrics::histogram!(
+        HTTP_REQUEST_DURATION_SECONDS,
+        started.elapsed().as_secs_f64(),
+        labels
+    );
+    response
+}
+// endregion: -- HTTP Metrics Middleware
+
+// region: -- SurrealDB Metrics
+/// Awaits `query`, recording how long it took under `operation`.
+pub async fn time_query<F: IntoFuture>(operation: &'static str, query: F) -> F::Output {
+    let started = Instant::now();
+    let output = query.await;
+    metrics::histogram!(
+        SURREALDB_QUERY_DURATION_SECONDS,
+        started.elapsed().as_secs_f64(),
+        "operation" => operation
+    );
+    output
+}
+// endregion: -- SurrealDB Metrics
+
+// region: -- Delivery Queue
+/// Tracks deliveries still pending in one publish run. Whatever is left when
+/// the run ends early (e.g. a send fails) is taken off the gauge on drop.
+pub struct DeliveryQueue {
+    pending: usize,
+}
+
+impl DeliveryQueue {
+    pub fn new(pending: usize) -> Self {
+        metrics::increment_gauge!(DELIVERY_QUEUE_DEPTH, pending as f64);
+        Self { pending }
+    }
+
+    /// One delivery left the queue, whether it was sent or skipped.
+    pub fn pop(&mut self) {
+        if self.pending > 0 {
+            self.pending -= 1;
+            metrics::decrement_gauge!(DELIVERY_QUEUE_DEPTH, 1.0);
+        }
+    }
+}
+
+impl Drop for DeliveryQueue {
+    fn drop(&mut self) {
+        metrics::decrement_gauge!(DELIVERY_QUEUE_DEPTH, self.pending as f64);
+    }
+}
+// endregion: -- Delivery Queue
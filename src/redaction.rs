@@ -0,0 +1,563 @@
+use std::{any::TypeId, collections::HashSet, fmt};
+
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use secrecy::{ExposeSecret, Secret};
+use serde_json::Value;
+use sha2::Sha256;
+use tracing::{
+    field::{display, DisplayValue, Field, FieldSet, Value as FieldValue, ValueSet, Visit},
+    level_filters::LevelFilter,
+    span::{Attributes, Id, Record},
+    subscriber::Interest,
+    Event, Metadata, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+use crate::configuration::{EmailRedaction, RedactionSettings};
+
+/// Fields `tracing` and its integrations add to every record. They are never
+/// redacted, though emails in them still are.
+const RECORD_FIELDS: &[&str] = &["message", "target", "line", "file", "busy_ns", "idle_ns"];
+const RECORD_FIELD_PREFIXES: &[&str] = &["log.", "code.", "thread.", "otel."];
+
+pub const DEFAULT_ALLOWED_FIELDS: &[&str] = &[
+    "request_id",
+    "method",
+    "uri",
+    "route",
+    "status",
+    "latency",
+    "classification",
+    "elapsed_milliseconds",
+    "error",
+    "err",
+    "error.cause_chain",
+    "e.cause_chain",
+    "cause_chain",
+    "subscriber_email",
+    "recipient",
+    "subject",
+    "title",
+    "action",
+    "admin",
+    "username",
+    "user_id",
+    "role",
+    "header",
+    "host",
+    "port",
+    "db",
+    "directive",
+    "ttl_seconds",
+    "outcome",
+];
+
+pub const DEFAULT_DROPPED_FIELDS: &[&str] = &[
+    "html_content",
+    "text_content",
+    "html_body",
+    "text_body",
+    "body",
+    "content",
+    "password",
+    "password_hash",
+    "token",
+    "subscription_token",
+    "secret",
+];
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Keys the email hash when `hash_key` is not configured.
+static PROCESS_HASH_KEY: Lazy<Vec<u8>> =
+    Lazy::new(|| rand::thread_rng().gen::<[u8; 32]>().to_vec());
+
+// region: -- Redaction Policy
+/// Decides what each tracing field may reveal. Dropped fields disappear,
+/// fields outside the allow-list keep their name but lose their value, and
+/// email addresses anywhere in what remains are hashed or masked.
+pub struct RedactionPolicy {
+    enabled: bool,
+    emails: EmailRedaction,
+    hash_key: Secret<Vec<u8>>,
+    allowed: HashSet<String>,
+    dropped: HashSet<String>,
+}
+
+impl RedactionPolicy {
+    pub fn new(settings: &RedactionSettings) -> Self {
+        Self {
+            enabled: settings.enabled,
+            emails: settings.emails,
+            hash_key: Secret::new(match &settings.hash_key {
+                Some(key) => key.expose_secret().as_bytes().to_vec(),
+                None => PROCESS_HASH_KEY.clone(),
+            }),
+            allowed: settings.allowed_fields.iter().cloned().collect(),
+            dropped: settings.dropped_fields.iter().cloned().collect(),
+        }
+    }
+
+    /// The value to record for `field`, or `None` to leave it out.
+    pub fn redact(&self, field: &str, value: Value) -> Option<Value> {
+        if !self.enabled {
+            return Some(value);
+        }
+        if self.dropped.contains(field) {
+            return None;
+        }
+        if !self.is_record_field(field) && !self.allowed.contains(field) {
+            return Some(Value::String(REDACTED.into()));
+        }
+        Some(self.scrub_value(value))
+    }
+
+    /// Replaces every email address in `text`.
+    pub fn scrub(&self, text: &str) -> String {
+        if !self.enabled || self.emails == EmailRedaction::Keep || !text.contains('@') {
+            return text.to_string();
+        }
+
+        let mut scrubbed = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some((start, end)) = find_email(rest) {
+            scrubbed.push_str(&rest[..start]);
+            scrubbed.push_str(&self.redact_email(&rest[start..end]));
+            rest = &rest[end..];
+        }
+        scrubbed.push_str(rest);
+        scrubbed
+    }
+
+    fn scrub_value(&self, value: Value) -> Value {
+        match value {
+            Value::String(text) => Value::String(self.scrub(&text)),
+            Value::Array(values) => {
+                Value::Array(values.into_iter().map(|v| self.scrub_value(v)).collect())
+            }
+            Value::Object(fields) => Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(k, v)| (k, self.scrub_value(v)))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+
+    fn is_record_field(&self, field: &str) -> bool {
+        RECORD_FIELDS.contains(&field)
+            || RECORD_FIELD_PREFIXES
+                .iter()
+                .any(|prefix| field.starts_with(prefix))
+    }
+
+    fn redact_email(&self, email: &str) -> String {
+        match self.emails {
+            EmailRedaction::Hash => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(self.hash_key.expose_secret())
+                    .expect("HMAC accepts keys of any length");
+                mac.update(email.to_lowercase().as_bytes());
+                format!("email:{:x}", mac.finalize().into_bytes())[..22].to_string()
+            }
+            EmailRedaction::Mask => {
+                let (local, domain) = email.split_once('@').unwrap_or((email, ""));
+                let first = local.chars().next().unwrap_or('*');
+                format!("{}***@{}", first, domain)
+            }
+            EmailRedaction::Keep => email.to_string(),
+        }
+    }
+}
+
+/// Byte range of the first email-like token in `text`.
+fn find_email(text: &str) -> Option<(usize, usize)> {
+    let is_local = |c: char| c.is_ascii_alphanumeric() || "._%+-".contains(c);
+    let is_domain = |c: char| c.is_ascii_alphanumeric() || ".-".contains(c);
+
+    let mut from = 0;
+    while let Some(offset) = text[from..].find('@') {
+        let at = from + offset;
+        let start = text[..at]
+            .rfind(|c: char| !is_local(c))
+            .map_or(0, |i| i + 1);
+        let end = text[at + 1..]
+            .find(|c: char| !is_domain(c))
+            .map_or(text.len(), |i| at + 1 + i);
+        let domain = text[at + 1..end].trim_end_matches('.');
+        if start < at && domain.contains('.') {
+            return Some((start, at + 1 + domain.len()));
+        }
+        from = at + 1;
+    }
+    None
+}
+// endregion: -- Redaction Policy
+
+// region: -- Redaction Layer
+/// Wraps the layers that record or ship tracing data (formatting,
+/// OpenTelemetry, Sentry) so they only ever see fields that went through the
+/// policy: every span, recorded value and event is rebuilt from its redacted
+/// fields before it is handed on.
+pub struct RedactionLayer<L> {
+    inner: L,
+    policy: RedactionPolicy,
+}
+
+impl<L> RedactionLayer<L> {
+    pub fn new(inner: L, policy: RedactionPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// `None` when there is nothing to redact: the policy is off or the
+    /// callsite has no fields.
+    fn redact_fields(
+        &self,
+        fields: &'static FieldSet,
+        record: impl FnOnce(&mut dyn Visit),
+    ) -> Option<RedactedFields> {
+        if !self.policy.enabled {
+            return None;
+        }
+        let placeholder = fields.iter().next()?;
+        let mut visitor = RedactingVisitor {
+            policy: &self.policy,
+            values: Vec::new(),
+        };
+        record(&mut visitor);
+        Some(RedactedFields {
+            fields,
+            placeholder,
+            values: visitor.values,
+        })
+    }
+}
+
+impl<S, L> Layer<S> for RedactionLayer<L>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    L: Layer<S>,
+{
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber)
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        self.inner.max_level_hint()
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let metadata = attrs.metadata();
+        match self.redact_fields(metadata.fields(), |visitor| attrs.record(visitor)) {
+            Some(fields) => fields.with_values(|values| {
+                let redacted = if attrs.is_contextual() {
+                    Attributes::new(metadata, values)
+                } else if let Some(parent) = attrs.parent() {
+                    Attributes::child_of(parent.clone(), metadata, values)
+                } else {
+                    Attributes::new_root(metadata, values)
+                };
+                self.inner.on_new_span(&redacted, id, ctx)
+            }),
+            None => self.inner.on_new_span(attrs, id, ctx),
+        }
+    }
+
+    fn on_record(&self, span: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let redacted = ctx.metadata(span).and_then(|metadata| {
+            self.redact_fields(metadata.fields(), |visitor| values.record(visitor))
+        });
+        match redacted {
+            Some(fields) => {
+                fields.with_values(|values| self.inner.on_record(span, &Record::new(values), ctx))
+            }
+            None => self.inner.on_record(span, values, ctx),
+        }
+    }
+
+    fn on_follows_from(&self, span: &Id, follows: &Id, ctx: Context<'_, S>) {
+        self.inner.on_follows_from(span, follows, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        match self.redact_fields(metadata.fields(), |visitor| event.record(visitor)) {
+            Some(fields) => fields.with_values(|values| {
+                let redacted = if event.is_contextual() {
+                    Event::new(metadata, values)
+                } else {
+                    Event::new_child_of(event.parent().cloned(), metadata, values)
+                };
+                self.inner.on_event(&redacted, ctx)
+            }),
+            None => self.inner.on_event(event, ctx),
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx)
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx)
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        self.inner.on_close(id, ctx)
+    }
+
+    fn on_id_change(&self, old: &Id, new: &Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx)
+    }
+
+    // Lets `OpenTelemetrySpanExt` find the OpenTelemetry layer inside
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            Some(self as *const Self as *const ())
+        } else {
+            self.inner.downcast_raw(id)
+        }
+    }
+}
+
+/// The fields of one span, record or event after redaction, ready to be
+/// turned back into a `ValueSet`.
+struct RedactedFields {
+    fields: &'static FieldSet,
+    /// Fills the unused slots of the fixed-size array a `ValueSet` is built from.
+    placeholder: Field,
+    values: Vec<(Field, RedactedValue)>,
+}
+
+impl RedactedFields {
+    fn with_values<R>(&self, f: impl FnOnce(&ValueSet<'_>) -> R) -> R {
+        // `tracing` callsites have at most 32 fields
+        let mut values: [(&Field, Option<&dyn FieldValue>); MAX_FIELDS] =
+            [(&self.placeholder, None); MAX_FIELDS];
+        for (slot, (field, value)) in values.iter_mut().zip(&self.values) {
+            *slot = (field, Some(value.as_value()));
+        }
+        f(&self.fields.value_set(&values))
+    }
+}
+
+const MAX_FIELDS: usize = 32;
+
+enum RedactedValue {
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Bool(bool),
+    Text(DisplayValue<String>),
+}
+
+impl RedactedValue {
+    fn from_json(value: Value) -> Self {
+        match value {
+            Value::Bool(value) => Self::Bool(value),
+            Value::Number(number) => number
+                .as_u64()
+                .map(Self::U64)
+                .or_else(|| number.as_i64().map(Self::I64))
+                .unwrap_or_else(|| Self::F64(number.as_f64().unwrap_or_default())),
+            Value::String(text) => Self::Text(display(text)),
+            value => Self::Text(display(value.to_string())),
+        }
+    }
+
+    fn as_value(&self) -> &dyn FieldValue {
+        match self {
+            Self::I64(value) => value,
+            Self::U64(value) => value,
+            Self::F64(value) => value,
+            Self::Bool(value) => value,
+            Self::Text(value) => value,
+        }
+    }
+}
+
+struct RedactingVisitor<'a> {
+    policy: &'a RedactionPolicy,
+    values: Vec<(Field, RedactedValue)>,
+}
+
+impl RedactingVisitor<'_> {
+    fn push(&mut self, field: &Field, value: Value) {
+        if let Some(value) = self.policy.redact(field.name(), value) {
+            self.values
+                .push((field.clone(), RedactedValue::from_json(value)));
+        }
+    }
+}
+
+impl Visit for RedactingVisitor<'_> {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, value.into())
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, value.into())
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        let value = serde_json::Number::from_f64(value)
+            .map_or_else(|| Value::String(value.to_string()), Value::Number);
+        self.push(field, value)
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, value.into())
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, value.into())
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.push(field, format!("{:?}", value).into())
+    }
+}
+// endregion: -- Redaction Layer
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::Map;
+    use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+    use super::*;
+
+    fn policy(emails: EmailRedaction) -> RedactionPolicy {
+        RedactionPolicy::new(&RedactionSettings {
+            emails,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn emails_are_hashed_or_masked_wherever_they_appear() {
+        let hashed = policy(EmailRedaction::Hash);
+        let masked = policy(EmailRedaction::Mask);
+        let text = "Failed to send newsletter to Ursula.Le-Guin@gmail.com.";
+
+        let scrubbed = hashed.scrub(text);
+        assert!(!scrubbed.contains("gmail"));
+        assert!(scrubbed.starts_with("Failed to send newsletter to email:"));
+        assert!(scrubbed.ends_with('.'));
+        assert_eq!(
+            hashed.scrub("ursula.le-guin@gmail.com"),
+            hashed.scrub("Ursula.Le-Guin@gmail.com")
+        );
+        assert_eq!(
+            masked.scrub(text),
+            "Failed to send newsletter to U***@gmail.com."
+        );
+        assert_eq!(masked.scrub("not @ an email"), "not @ an email");
+    }
+
+    #[test]
+    fn bodies_are_dropped_and_unknown_fields_redacted() {
+        let policy = policy(EmailRedaction::Hash);
+
+        assert_eq!(policy.redact("html_content", "<p>Hi</p>".into()), None);
+        assert_eq!(
+            policy.redact("subscriber_name", "le guin".into()),
+            Some(REDACTED.into())
+        );
+        assert_eq!(policy.redact("status", 200.into()), Some(200.into()));
+        assert_eq!(policy.redact("log.line", 12.into()), Some(12.into()));
+    }
+
+    #[test]
+    fn email_hashes_depend_on_the_key() {
+        let hash = |key: &str| {
+            RedactionPolicy::new(&RedactionSettings {
+                hash_key: Some(Secret::new(key.into())),
+                ..Default::default()
+            })
+            .scrub("ursula@gmail.com")
+        };
+
+        assert_eq!(hash("one"), hash("one"));
+        assert_ne!(hash("one"), hash("two"));
+        assert_eq!(hash("one").len(), 22);
+    }
+
+    #[test]
+    fn layers_behind_the_redaction_layer_only_see_redacted_fields() {
+        let captured = Captured::default();
+        let subscriber = Registry::default().with(RedactionLayer::new(
+            captured.clone(),
+            policy(EmailRedaction::Mask),
+        ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "Sending email",
+                recipient = "ursula@gmail.com",
+                html_content = "<p>Hi</p>",
+                nickname = tracing::field::Empty,
+            );
+            span.record("nickname", "u");
+            tracing::info!(status = 200, "Sent to {}", "ursula@gmail.com");
+        });
+
+        let records = captured.0.lock().unwrap();
+        assert_eq!(records[0]["recipient"], "u***@gmail.com");
+        assert!(records[0].get("html_content").is_none());
+        assert_eq!(records[1]["nickname"], REDACTED);
+        assert_eq!(records[2]["message"], "Sent to u***@gmail.com");
+        assert_eq!(records[2]["status"], 200);
+    }
+
+    /// Collects the fields of every span, record and event it sees.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<Map<String, Value>>>>);
+
+    impl Captured {
+        fn push(&self, record: impl FnOnce(&mut dyn Visit)) {
+            let mut fields = CapturedFields::default();
+            record(&mut fields);
+            self.0.lock().unwrap().push(fields.0);
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Captured {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+            self.push(|visitor| attrs.record(visitor));
+        }
+
+        fn on_record(&self, _: &Id, values: &Record<'_>, _: Context<'_, S>) {
+            self.push(|visitor| values.record(visitor));
+        }
+
+        fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+            self.push(|visitor| event.record(visitor));
+        }
+    }
+
+    #[derive(Default)]
+    struct CapturedFields(Map<String, Value>);
+
+    impl Visit for CapturedFields {
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            self.0.insert(field.name().into(), value.into());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().into(), format!("{:?}", value).into());
+        }
+    }
+}
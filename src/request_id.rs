@@ -0,0 +1,97 @@
+use axum::{
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use hyper::Body;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longer incoming ids are replaced rather than trusted.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: RequestId;
+}
+
+// region: -- Request Id
+/// Correlates one request across our logs, the response and outbound calls.
+#[derive(Clone, Debug)]
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The id of the request being handled by the current task, if any.
+    pub fn current() -> Option<RequestId> {
+        CURRENT_REQUEST_ID.try_with(|id| id.clone()).ok()
+    }
+
+    fn from_header(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?.trim();
+        let valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LENGTH
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+        valid.then(|| Self(value.to_string()))
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+// endregion: -- Request Id
+
+// region: -- Request Id Middleware
+/// Honours a well-formed incoming `X-Request-Id` or generates one, exposes it
+/// to handlers and outbound clients, and echoes it on the response. Error
+/// bodies quote it too, see `problem::render_problems`.
+pub async fn propagate_request_id(mut request: Request<Body>, next: Next<Body>) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(RequestId::from_header)
+        .unwrap_or_else(|| RequestId(Uuid::new_v4().to_string()));
+
+    let header_value =
+        HeaderValue::from_str(request_id.as_str()).expect("Request ids are valid header values.");
+    request
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, header_value.clone());
+    request.extensions_mut().insert(request_id.clone());
+
+    let mut response = CURRENT_REQUEST_ID
+        .scope(request_id, next.run(request))
+        .await;
+
+    response
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, header_value);
+    response
+}
+// endregion: -- Request Id Middleware
+
+#[cfg(test)]
+mod tests {
+    use super::RequestId;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn only_well_formed_incoming_ids_are_honoured() {
+        let id = |value: &str| RequestId::from_header(&HeaderValue::from_str(value).unwrap());
+
+        assert_eq!(
+            id("3f2b9c1e-support.ticket:42").unwrap().as_str(),
+            "3f2b9c1e-support.ticket:42"
+        );
+        assert!(id("").is_none());
+        assert!(id("has spaces").is_none());
+        assert!(id(&"a".repeat(129)).is_none());
+    }
+}
@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use axum_macros::debug_handler;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audit::{self, AuditFilter, AuditRecord},
+    authentication::{Authorized, CanManageUsers},
+    db::Database,
+    error::AdminError,
+    startup::AppState,
+};
+
+const MAX_PER_PAGE: u32 = 200;
+
+#[derive(Deserialize, Debug)]
+pub struct AuditQuery {
+    actor: Option<String>,
+    action: Option<String>,
+    target: Option<String>,
+    /// RFC 3339 timestamps; anything else is rejected with a 400
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    #[serde(default = "default_page")]
+    page: u32,
+    #[serde(default = "default_per_page")]
+    per_page: u32,
+}
+
+fn default_page() -> u32 {
+    1
+}
+
+fn default_per_page() -> u32 {
+    50
+}
+
+#[derive(Serialize)]
+pub struct AuditPage {
+    entries: Vec<AuditRecord>,
+    page: u32,
+    per_page: u32,
+}
+
+// region: -- GET /admin/audit
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: query audit log",
+    skip(admin, database),
+    fields(admin = %admin.user.username)
+)]
+pub async fn admin_audit_log(
+    admin: Authorized<CanManageUsers>,
+    State(database): State<Database>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditPage>, AdminError> {
+    let page = query.page.max(1);
+    let per_page = query.per_page.clamp(1, MAX_PER_PAGE);
+    let filter = AuditFilter {
+        actor: query.actor,
+        action: query.action,
+        target: query.target,
+        since: query.since,
+        until: query.until,
+    };
+    let entries = audit::query(&database.client, &filter, page, per_page).await?;
+
+    Ok(Json(AuditPage {
+        entries,
+        page,
+        per_page,
+    }))
+}
+// endregion: -- GET /admin/audit
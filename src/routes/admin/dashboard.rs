@@ -0,0 +1,106 @@
+use askama::Template;
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse, Response},
+};
+use axum_macros::debug_handler;
+use color_eyre::eyre::Context;
+use serde::Deserialize;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+use crate::{
+    authentication::{Authorized, CanView},
+    csrf::CsrfToken,
+    db::Database,
+    error::AdminError,
+    flash::{Flash, FlashMessage},
+    security_headers::CspNonce,
+    startup::AppState,
+};
+
+#[derive(Deserialize, Debug)]
+pub struct StatusCount {
+    pub status: String,
+    pub count: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RecentSignup {
+    pub email: String,
+    pub name: String,
+    pub status: String,
+    pub subscribed_at: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/dashboard.html")]
+struct DashboardTemplate {
+    username: String,
+    flash: Vec<FlashMessage>,
+    csrf_token: String,
+    csp_nonce: String,
+    status_counts: Vec<StatusCount>,
+    recent_signups: Vec<RecentSignup>,
+}
+
+// region: -- GET /admin/dashboard
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: dashboard",
+    skip(admin, database, flash, csrf, nonce),
+    fields(admin = %admin.user.username)
+)]
+pub async fn admin_dashboard(
+    admin: Authorized<CanView>,
+    State(database): State<Database>,
+    mut flash: Flash,
+    csrf: CsrfToken,
+    nonce: CspNonce,
+) -> Result<Response, AdminError> {
+    let status_counts = get_status_counts(&database.client)
+        .await
+        .context("Failed to count subscribers by status.")?;
+    let recent_signups = get_recent_signups(&database.client, 10)
+        .await
+        .context("Failed to retrieve recent sign-ups.")?;
+
+    let html = DashboardTemplate {
+        username: admin.user.username,
+        flash: flash.take(),
+        csrf_token: csrf.to_string(),
+        csp_nonce: nonce.to_string(),
+        status_counts,
+        recent_signups,
+    }
+    .render()
+    .context("Failed to render the dashboard")?;
+
+    Ok(Html(html).into_response())
+}
+// endregion: -- GET /admin/dashboard
+
+// region: -- Subscriber Statistics (SurrealDB Retrieve)
+#[tracing::instrument(name = "Count subscribers by status", skip(conn))]
+pub async fn get_status_counts(
+    conn: &Surreal<Client>,
+) -> Result<Vec<StatusCount>, surrealdb::Error> {
+    let sql = "SELECT status, count() AS count FROM subscriptions GROUP BY status";
+
+    let mut res = conn.query(sql).await?.check()?;
+    let counts: Vec<StatusCount> = res.take(0)?;
+    Ok(counts)
+}
+
+#[tracing::instrument(name = "Retrieve recent sign-ups", skip(conn))]
+pub async fn get_recent_signups(
+    conn: &Surreal<Client>,
+    limit: usize,
+) -> Result<Vec<RecentSignup>, surrealdb::Error> {
+    let sql = "SELECT email, name, status, <string> subscribed_at AS subscribed_at \
+        FROM subscriptions ORDER BY subscribed_at DESC LIMIT $limit";
+
+    let mut res = conn.query(sql).bind(("limit", limit)).await?.check()?;
+    let signups: Vec<RecentSignup> = res.take(0)?;
+    Ok(signups)
+}
+// endregion: -- Subscriber Statistics (SurrealDB Retrieve)
@@ -0,0 +1,84 @@
+use askama::Template;
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse, Response},
+};
+use axum_macros::debug_handler;
+use color_eyre::eyre::Context;
+use serde::Deserialize;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+use crate::{
+    authentication::{Authorized, CanView},
+    csrf::CsrfToken,
+    db::Database,
+    error::AdminError,
+    flash::{Flash, FlashMessage},
+    security_headers::CspNonce,
+    startup::AppState,
+};
+
+#[derive(Deserialize, Debug)]
+pub struct IssueSummary {
+    pub title: String,
+    pub published_by: String,
+    pub recipients: i64,
+    pub published_at: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/issues.html")]
+struct IssuesTemplate {
+    username: String,
+    flash: Vec<FlashMessage>,
+    csrf_token: String,
+    csp_nonce: String,
+    issues: Vec<IssueSummary>,
+}
+
+// region: -- GET /admin/issues
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: issue history",
+    skip(admin, database, flash, csrf, nonce),
+    fields(admin = %admin.user.username)
+)]
+pub async fn admin_issues(
+    admin: Authorized<CanView>,
+    State(database): State<Database>,
+    mut flash: Flash,
+    csrf: CsrfToken,
+    nonce: CspNonce,
+) -> Result<Response, AdminError> {
+    let issues = get_issue_history(&database.client, 50)
+        .await
+        .context("Failed to retrieve the issue history.")?;
+
+    let html = IssuesTemplate {
+        username: admin.user.username,
+        flash: flash.take(),
+        csrf_token: csrf.to_string(),
+        csp_nonce: nonce.to_string(),
+        issues,
+    }
+    .render()
+    .context("Failed to render the issue history")?;
+
+    Ok(Html(html).into_response())
+}
+// endregion: -- GET /admin/issues
+
+// region: -- Issue History (SurrealDB Retrieve)
+#[tracing::instrument(name = "Retrieve issue history", skip(conn))]
+pub async fn get_issue_history(
+    conn: &Surreal<Client>,
+    limit: usize,
+) -> Result<Vec<IssueSummary>, surrealdb::Error> {
+    let sql = "SELECT title, published_by, recipients, <string> published_at AS published_at \
+        FROM newsletter_issues ORDER BY published_at DESC LIMIT $limit";
+
+    let mut res = conn.query(sql).bind(("limit", limit)).await?.check()?;
+    let issues: Vec<IssueSummary> = res.take(0)?;
+    Ok(issues)
+}
+// endregion: -- Issue History (SurrealDB Retrieve)
@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::request::Parts,
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_macros::debug_handler;
+use color_eyre::eyre::eyre;
+use serde::Deserialize;
+
+use crate::{
+    audit::{self, AuditContext, AuditEntry},
+    authentication::{
+        authenticate_api_token, bearer_token, require_role, ApiTokenScope, Authorized,
+        CanManageUsers, Role,
+    },
+    db::Database,
+    error::{AdminError, ApiTokenError, AuthError},
+    log_filter::{LogFilter, LogFilterError, LogFilterStatus},
+    startup::AppState,
+};
+
+/// The longest a changed filter may stay in place before reverting.
+const MAX_TTL_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Deserialize, Debug)]
+pub struct LogLevelData {
+    /// A `RUST_LOG` style directive, e.g. `info,zero2axum=debug`.
+    directive: Option<String>,
+    ttl_seconds: Option<u64>,
+    /// Go back to the directive the server started with.
+    #[serde(default)]
+    reset: bool,
+}
+
+// region: -- Log Filter Admin Extractor
+/// An owner, either logged in or presenting a `logging:write` API token.
+pub struct LogFilterAdmin {
+    pub username: String,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for LogFilterAdmin {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(token) = bearer_token(&parts.headers) else {
+            let admin = Authorized::<CanManageUsers>::from_request_parts(parts, state).await?;
+            return Ok(Self {
+                username: admin.user.username,
+            });
+        };
+
+        let owner =
+            authenticate_api_token(&token, ApiTokenScope::LoggingWrite, &state.database.client)
+                .await
+                .map_err(|e| match e {
+                    AuthError::InvalidCredentials(_) => ApiTokenError::AuthError(e.into()),
+                    AuthError::UnexpectedError(_) => ApiTokenError::UnexpectedError(e.into()),
+                })
+                .map_err(IntoResponse::into_response)?;
+        require_role(owner.role, Role::Owner).map_err(|e| {
+            tracing::warn!(username = %owner.username, error = %e, "Forbidden");
+            e.into_response()
+        })?;
+
+        Ok(Self {
+            username: owner.username,
+        })
+    }
+}
+// endregion: -- Log Filter Admin Extractor
+
+// region: -- GET /admin/log-level
+#[debug_handler(state = AppState)]
+pub async fn admin_log_level(_admin: LogFilterAdmin) -> Result<Json<LogFilterStatus>, AdminError> {
+    let filter = LogFilter::global().map_err(into_admin_error)?;
+    Ok(Json(filter.status()))
+}
+// endregion: -- GET /admin/log-level
+
+// region: -- PUT /admin/log-level
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: change log filter",
+    skip(admin, database, audit_context, data),
+    fields(admin = %admin.username)
+)]
+pub async fn admin_set_log_level(
+    admin: LogFilterAdmin,
+    State(database): State<Database>,
+    audit_context: AuditContext,
+    Json(data): Json<LogLevelData>,
+) -> Result<Json<LogFilterStatus>, AdminError> {
+    if data.ttl_seconds.map_or(false, |ttl| ttl > MAX_TTL_SECONDS) {
+        return Err(AdminError::ValidationError(format!(
+            "`ttl_seconds` must be at most {MAX_TTL_SECONDS}."
+        )));
+    }
+
+    let filter = LogFilter::global().map_err(into_admin_error)?;
+    let previous = filter.status().directive;
+
+    let status = match (data.reset, &data.directive) {
+        (true, _) => filter.reset(),
+        (false, Some(directive)) => {
+            filter.set(directive, data.ttl_seconds.map(Duration::from_secs))
+        }
+        (false, None) => {
+            return Err(AdminError::ValidationError(
+                "Provide a `directive` or set `reset`.".into(),
+            ))
+        }
+    }
+    .map_err(into_admin_error)?;
+
+    audit::record(
+        &database.client,
+        &audit_context,
+        AuditEntry::new("logging.filter_change", &admin.username).diff(serde_json::json!({
+            "directive": { "from": previous, "to": status.directive },
+            "ttl_seconds": data.ttl_seconds.filter(|_| !data.reset),
+        })),
+    )
+    .await;
+
+    Ok(Json(status))
+}
+// endregion: -- PUT /admin/log-level
+
+fn into_admin_error(e: LogFilterError) -> AdminError {
+    match e {
+        LogFilterError::InvalidDirective(_) | LogFilterError::InvalidTtl => {
+            AdminError::ValidationError(e.to_string())
+        }
+        LogFilterError::Unavailable => AdminError::UnexpectedError(eyre!(e)),
+    }
+}
@@ -0,0 +1,21 @@
+mod audit;
+mod dashboard;
+mod issues;
+mod log_level;
+mod newsletters;
+mod password;
+mod subscribers;
+mod tokens;
+mod two_factor;
+mod users;
+
+pub use audit::*;
+pub use dashboard::*;
+pub use issues::*;
+pub use log_level::*;
+pub use newsletters::*;
+pub use password::*;
+pub use subscribers::*;
+pub use tokens::*;
+pub use two_factor::*;
+pub use users::*;
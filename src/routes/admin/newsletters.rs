@@ -0,0 +1,112 @@
+use askama::Template;
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse, Redirect, Response},
+    Form,
+};
+use axum_macros::debug_handler;
+use color_eyre::eyre::Context;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::{
+    audit::{self, AuditContext, AuditEntry},
+    authentication::{Authorized, CanPublish},
+    csrf::CsrfToken,
+    db::Database,
+    email_client::EmailClient,
+    error::AdminError,
+    flash::{Flash, FlashMessage},
+    routes::{publish_issue, NewsletterIssue},
+    security_headers::CspNonce,
+    startup::AppState,
+};
+
+#[derive(Deserialize)]
+pub struct PublishFormData {
+    title: String,
+    text_content: String,
+    html_content: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/newsletter.html")]
+struct NewsletterFormTemplate {
+    username: String,
+    flash: Vec<FlashMessage>,
+    csrf_token: String,
+    csp_nonce: String,
+}
+
+// region: -- GET /admin/newsletters
+#[debug_handler(state = AppState)]
+pub async fn admin_newsletter_form(
+    admin: Authorized<CanPublish>,
+    mut flash: Flash,
+    csrf: CsrfToken,
+    nonce: CspNonce,
+) -> Result<Response, AdminError> {
+    let html = NewsletterFormTemplate {
+        username: admin.user.username,
+        flash: flash.take(),
+        csrf_token: csrf.to_string(),
+        csp_nonce: nonce.to_string(),
+    }
+    .render()
+    .context("Failed to render the newsletter form")?;
+
+    Ok(Html(html).into_response())
+}
+// endregion: -- GET /admin/newsletters
+
+// region: -- POST /admin/newsletters
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: publish newsletter",
+    skip(admin, database, email_client, audit_context, flash, form),
+    fields(admin = %admin.user.username)
+)]
+pub async fn admin_publish_newsletter(
+    admin: Authorized<CanPublish>,
+    State(database): State<Database>,
+    State(email_client): State<Arc<EmailClient>>,
+    audit_context: AuditContext,
+    mut flash: Flash,
+    Form(form): Form<PublishFormData>,
+) -> Result<Response, AdminError> {
+    if form.title.trim().is_empty()
+        || form.text_content.trim().is_empty()
+        || form.html_content.trim().is_empty()
+    {
+        flash.error("The title and both contents are required.");
+        return Ok(Redirect::to("/admin/newsletters").into_response());
+    }
+
+    let recipients = publish_issue(
+        &database,
+        &email_client,
+        &NewsletterIssue {
+            title: &form.title,
+            html_content: &form.html_content,
+            text_content: &form.text_content,
+        },
+        &admin.user.username,
+    )
+    .await?;
+
+    audit::record(
+        &database.client,
+        &audit_context,
+        AuditEntry::new("newsletter.publish", &admin.user.username)
+            .target(&form.title)
+            .diff(serde_json::json!({ "recipients": recipients, "via": "form" })),
+    )
+    .await;
+
+    flash.success(format!(
+        "The newsletter issue has been published to {} subscribers.",
+        recipients
+    ));
+    Ok(Redirect::to("/admin/issues").into_response())
+}
+// endregion: -- POST /admin/newsletters
@@ -0,0 +1,141 @@
+use askama::Template;
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse, Redirect, Response},
+    Form,
+};
+use axum_macros::debug_handler;
+use color_eyre::eyre::Context;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::{
+    audit::{self, AuditContext, AuditEntry},
+    authentication::{
+        change_password, validate_credentials, validate_password, AdminUser, Credentials,
+    },
+    configuration::Settings,
+    csrf::CsrfToken,
+    db::Database,
+    error::{AdminError, AuthError, UserError},
+    flash::{Flash, FlashMessage},
+    security_headers::CspNonce,
+    session::{Session, SessionStore},
+    startup::AppState,
+};
+
+#[derive(Deserialize)]
+pub struct ChangePasswordFormData {
+    current_password: Secret<String>,
+    new_password: Secret<String>,
+    new_password_check: Secret<String>,
+}
+
+#[derive(Template)]
+#[template(path = "admin/password.html")]
+struct ChangePasswordTemplate {
+    username: String,
+    flash: Vec<FlashMessage>,
+    csrf_token: String,
+    csp_nonce: String,
+}
+
+// region: -- GET /admin/password
+#[debug_handler(state = AppState)]
+pub async fn admin_change_password_form(
+    admin: AdminUser,
+    mut flash: Flash,
+    csrf: CsrfToken,
+    nonce: CspNonce,
+) -> Result<Response, AdminError> {
+    let html = ChangePasswordTemplate {
+        username: admin.username,
+        flash: flash.take(),
+        csrf_token: csrf.to_string(),
+        csp_nonce: nonce.to_string(),
+    }
+    .render()
+    .context("Failed to render the change password form")?;
+
+    Ok(Html(html).into_response())
+}
+// endregion: -- GET /admin/password
+
+// region: -- POST /admin/password
+#[allow(clippy::too_many_arguments)]
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: change own password",
+    skip(admin, session, database, configuration, session_store, audit_context, flash, form),
+    fields(admin = %admin.username)
+)]
+pub async fn admin_change_password(
+    admin: AdminUser,
+    session: Session,
+    State(database): State<Database>,
+    State(configuration): State<Settings>,
+    State(session_store): State<Arc<dyn SessionStore>>,
+    audit_context: AuditContext,
+    mut flash: Flash,
+    Form(form): Form<ChangePasswordFormData>,
+) -> Result<Response, AdminError> {
+    let retry = || Ok(Redirect::to("/admin/password").into_response());
+
+    if form.new_password.expose_secret() != form.new_password_check.expose_secret() {
+        flash.error("You entered two different new passwords - the field values must match.");
+        return retry();
+    }
+
+    let credentials = Credentials {
+        username: admin.username.clone(),
+        password: form.current_password,
+    };
+    match validate_credentials(
+        credentials,
+        &configuration.authentication.argon2,
+        &database.client,
+    )
+    .await
+    {
+        Ok(_) => {}
+        Err(AuthError::InvalidCredentials(_)) => {
+            flash.error("The current password is incorrect.");
+            return retry();
+        }
+        Err(AuthError::UnexpectedError(e)) => return Err(AdminError::UnexpectedError(e)),
+    }
+
+    if let Err(UserError::ValidationError(message)) = validate_password(
+        &form.new_password,
+        &configuration.authentication.password_policy,
+    ) {
+        flash.error(message);
+        return retry();
+    }
+
+    change_password(
+        &admin.username,
+        form.new_password,
+        &configuration.authentication,
+        &database.client,
+    )
+    .await
+    .map_err(|e| AdminError::UnexpectedError(e.into()))?;
+
+    // Anyone else holding a session for this user must log in again
+    session_store
+        .delete_for_user(&admin.user_id, session.id())
+        .await?;
+
+    audit::record(
+        &database.client,
+        &audit_context,
+        AuditEntry::new("user.password_change", &admin.username).target(&admin.username),
+    )
+    .await;
+
+    flash.success("Your password has been changed.");
+    retry()
+}
+// endregion: -- POST /admin/password
@@ -0,0 +1,83 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_macros::debug_handler;
+use serde::Deserialize;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+use crate::{
+    audit::{self, change, AuditContext, AuditEntry},
+    authentication::{Authorized, CanPublish},
+    db::Database,
+    error::AdminError,
+    startup::AppState,
+};
+
+const SUBSCRIBER_STATUSES: [&str; 2] = ["pending_confirmation", "confirmed"];
+
+#[derive(Deserialize)]
+pub struct SubscriberStatusData {
+    status: String,
+}
+
+// region: -- PUT /admin/subscribers/:email/status
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: override subscriber status",
+    skip(admin, database, audit_context, data),
+    fields(admin = %admin.user.username, status = %data.status)
+)]
+pub async fn admin_set_subscriber_status(
+    admin: Authorized<CanPublish>,
+    State(database): State<Database>,
+    audit_context: AuditContext,
+    Path(email): Path<String>,
+    Json(data): Json<SubscriberStatusData>,
+) -> Result<Response, AdminError> {
+    if !SUBSCRIBER_STATUSES.contains(&data.status.as_str()) {
+        return Err(AdminError::ValidationError(format!(
+            "`{}` is not a valid subscriber status.",
+            data.status
+        )));
+    }
+
+    let previous = set_subscriber_status(&email, &data.status, &database.client)
+        .await?
+        .ok_or(AdminError::UnknownSubscriber)?;
+
+    audit::record(
+        &database.client,
+        &audit_context,
+        AuditEntry::new("subscriber.status_override", &admin.user.username)
+            .target(&email)
+            .diff(change("status", previous, &data.status)),
+    )
+    .await;
+
+    Ok(StatusCode::OK.into_response())
+}
+// endregion: -- PUT /admin/subscribers/:email/status
+
+// region: -- Set Subscriber Status (SurrealDB Update)
+/// Returns the previous status, or `None` if there is no such subscriber.
+#[tracing::instrument(name = "Override subscriber status", skip(conn))]
+pub async fn set_subscriber_status(
+    email: &str,
+    status: &str,
+    conn: &Surreal<Client>,
+) -> Result<Option<String>, surrealdb::Error> {
+    let sql = "UPDATE subscriptions SET status = $status WHERE email = $email RETURN BEFORE";
+
+    let mut res = conn
+        .query(sql)
+        .bind(("email", email))
+        .bind(("status", status))
+        .await?
+        .check()?;
+    let previous: Option<String> = res.take((0, "status"))?;
+    Ok(previous)
+}
+// endregion: -- Set Subscriber Status (SurrealDB Update)
@@ -0,0 +1,108 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_macros::debug_handler;
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+
+use crate::{
+    audit::{self, AuditContext, AuditEntry},
+    authentication::{
+        create_api_token, list_api_tokens, revoke_api_token, AdminUser, ApiTokenScope,
+        ApiTokenSummary,
+    },
+    db::Database,
+    error::ApiTokenError,
+    startup::AppState,
+};
+
+#[derive(Deserialize)]
+pub struct CreateTokenData {
+    name: String,
+    scopes: Vec<ApiTokenScope>,
+    expires_in_days: Option<u32>,
+}
+
+// region: -- GET /admin/tokens
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: list API tokens", skip(admin, database), fields(admin = %admin.username))]
+pub async fn admin_list_tokens(
+    admin: AdminUser,
+    State(database): State<Database>,
+) -> Result<Json<Vec<ApiTokenSummary>>, ApiTokenError> {
+    Ok(Json(
+        list_api_tokens(&admin.user_id, &database.client).await?,
+    ))
+}
+// endregion: -- GET /admin/tokens
+
+// region: -- POST /admin/tokens
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: create API token",
+    skip(admin, database, audit_context, data),
+    fields(admin = %admin.username, name = %data.name)
+)]
+pub async fn admin_create_token(
+    admin: AdminUser,
+    State(database): State<Database>,
+    audit_context: AuditContext,
+    Json(data): Json<CreateTokenData>,
+) -> Result<Response, ApiTokenError> {
+    let (summary, token) = create_api_token(
+        &admin.user_id,
+        &data.name,
+        &data.scopes,
+        data.expires_in_days,
+        &database.client,
+    )
+    .await?;
+
+    audit::record(
+        &database.client,
+        &audit_context,
+        AuditEntry::new("token.create", &admin.username)
+            .target(&summary.id)
+            .diff(serde_json::json!({
+                "name": summary.name,
+                "scopes": summary.scopes,
+                "expires_at": summary.expires_at,
+            })),
+    )
+    .await;
+
+    // The only time the token is ever shown
+    let body = serde_json::json!({
+        "id": summary.id,
+        "name": summary.name,
+        "scopes": summary.scopes,
+        "expires_at": summary.expires_at,
+        "token": token.expose_secret(),
+    });
+    Ok((StatusCode::CREATED, Json(body)).into_response())
+}
+// endregion: -- POST /admin/tokens
+
+// region: -- DELETE /admin/tokens/:id
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: revoke API token", skip(admin, database, audit_context), fields(admin = %admin.username))]
+pub async fn admin_revoke_token(
+    admin: AdminUser,
+    State(database): State<Database>,
+    audit_context: AuditContext,
+    Path(token_id): Path<String>,
+) -> Result<Response, ApiTokenError> {
+    revoke_api_token(&admin.user_id, &token_id, &database.client).await?;
+
+    audit::record(
+        &database.client,
+        &audit_context,
+        AuditEntry::new("token.revoke", &admin.username).target(&token_id),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+// endregion: -- DELETE /admin/tokens/:id
@@ -0,0 +1,152 @@
+use askama::Template;
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse, Redirect, Response},
+    Form,
+};
+use axum_macros::debug_handler;
+use color_eyre::eyre::Context;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+
+use crate::{
+    audit::{self, AuditContext, AuditEntry},
+    authentication::{begin_totp_enrollment, confirm_totp_enrollment, get_totp_secret, AdminUser},
+    csrf::CsrfToken,
+    db::Database,
+    error::AdminError,
+    flash::{Flash, FlashMessage},
+    security_headers::CspNonce,
+    startup::AppState,
+};
+
+#[derive(Deserialize)]
+pub struct EnableTwoFactorFormData {
+    code: Secret<String>,
+}
+
+#[derive(Template)]
+#[template(path = "admin/two_factor.html")]
+struct TwoFactorTemplate {
+    username: String,
+    flash: Vec<FlashMessage>,
+    csrf_token: String,
+    csp_nonce: String,
+    enrollment: Option<Enrollment>,
+}
+
+struct Enrollment {
+    secret: String,
+    provisioning_uri: String,
+    qr_code: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin/recovery_codes.html")]
+struct RecoveryCodesTemplate {
+    username: String,
+    flash: Vec<FlashMessage>,
+    csrf_token: String,
+    csp_nonce: String,
+    recovery_codes: Vec<String>,
+}
+
+// region: -- GET /admin/two-factor
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: two-factor settings",
+    skip(admin, database, flash, csrf, nonce),
+    fields(admin = %admin.username)
+)]
+pub async fn admin_two_factor_form(
+    admin: AdminUser,
+    State(database): State<Database>,
+    mut flash: Flash,
+    csrf: CsrfToken,
+    nonce: CspNonce,
+) -> Result<Response, AdminError> {
+    let enabled = get_totp_secret(&admin.user_id, &database.client)
+        .await?
+        .is_some();
+
+    // Every visit starts a fresh enrollment, so an abandoned QR code is never
+    // the one that ends up enabled
+    let enrollment = if enabled {
+        None
+    } else {
+        let enrollment =
+            begin_totp_enrollment(&admin.user_id, &admin.username, &database.client).await?;
+        Some(Enrollment {
+            secret: enrollment.secret.expose_secret().clone(),
+            provisioning_uri: enrollment.provisioning_uri,
+            qr_code: enrollment.qr_code,
+        })
+    };
+
+    let html = TwoFactorTemplate {
+        username: admin.username,
+        flash: flash.take(),
+        csrf_token: csrf.to_string(),
+        csp_nonce: nonce.to_string(),
+        enrollment,
+    }
+    .render()
+    .context("Failed to render the two-factor settings")?;
+
+    Ok(Html(html).into_response())
+}
+// endregion: -- GET /admin/two-factor
+
+// region: -- POST /admin/two-factor
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: enable two-factor authentication",
+    skip(admin, database, audit_context, flash, csrf, nonce, form),
+    fields(admin = %admin.username)
+)]
+pub async fn admin_enable_two_factor(
+    admin: AdminUser,
+    State(database): State<Database>,
+    audit_context: AuditContext,
+    mut flash: Flash,
+    csrf: CsrfToken,
+    nonce: CspNonce,
+    Form(form): Form<EnableTwoFactorFormData>,
+) -> Result<Response, AdminError> {
+    let recovery_codes = confirm_totp_enrollment(
+        &admin.user_id,
+        &admin.username,
+        &form.code,
+        &database.client,
+    )
+    .await?;
+
+    let Some(recovery_codes) = recovery_codes else {
+        flash.error("The code did not match - scan the new QR code and try again.");
+        return Ok(Redirect::to("/admin/two-factor").into_response());
+    };
+
+    audit::record(
+        &database.client,
+        &audit_context,
+        AuditEntry::new("user.two_factor_enable", &admin.username).target(&admin.username),
+    )
+    .await;
+
+    flash.success("Two-factor authentication is now enabled.");
+    let html = RecoveryCodesTemplate {
+        username: admin.username,
+        flash: flash.take(),
+        csrf_token: csrf.to_string(),
+        csp_nonce: nonce.to_string(),
+        recovery_codes: recovery_codes
+            .iter()
+            .map(|code| code.expose_secret().clone())
+            .collect(),
+    }
+    .render()
+    .context("Failed to render the recovery codes")?;
+
+    Ok(Html(html).into_response())
+}
+// endregion: -- POST /admin/two-factor
@@ -0,0 +1,193 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_macros::debug_handler;
+use secrecy::Secret;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::{
+    audit::{self, change, AuditContext, AuditEntry},
+    authentication::{
+        change_password, create_user, deactivate_user, list_users, set_user_role, Authorized,
+        CanManageUsers, Role, UserSummary,
+    },
+    configuration::Settings,
+    db::Database,
+    error::UserError,
+    session::SessionStore,
+    startup::AppState,
+};
+
+#[derive(Deserialize)]
+pub struct CreateUserData {
+    username: String,
+    password: Secret<String>,
+    /// Defaults to the least privileged role.
+    role: Option<Role>,
+}
+
+#[derive(Deserialize)]
+pub struct PasswordData {
+    password: Secret<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RoleData {
+    role: Role,
+}
+
+// region: -- GET /admin/users
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: list users", skip(admin, database), fields(admin = %admin.user.username))]
+pub async fn admin_list_users(
+    admin: Authorized<CanManageUsers>,
+    State(database): State<Database>,
+) -> Result<Json<Vec<UserSummary>>, UserError> {
+    Ok(Json(list_users(&database.client).await?))
+}
+// endregion: -- GET /admin/users
+
+// region: -- POST /admin/users
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: create user",
+    skip(admin, database, configuration, audit_context, data),
+    fields(admin = %admin.user.username, username = %data.username)
+)]
+pub async fn admin_create_user(
+    admin: Authorized<CanManageUsers>,
+    State(database): State<Database>,
+    State(configuration): State<Settings>,
+    audit_context: AuditContext,
+    Json(data): Json<CreateUserData>,
+) -> Result<Response, UserError> {
+    let role = data.role.unwrap_or(Role::Viewer);
+    let user_id = create_user(
+        &data.username,
+        data.password,
+        role,
+        &configuration.authentication,
+        &database.client,
+    )
+    .await?;
+
+    audit::record(
+        &database.client,
+        &audit_context,
+        AuditEntry::new("user.create", &admin.user.username)
+            .target(&data.username)
+            .diff(change("role", None::<Role>, role)),
+    )
+    .await;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "id": user_id.to_string(),
+            "username": data.username,
+            "role": role,
+        })),
+    )
+        .into_response())
+}
+// endregion: -- POST /admin/users
+
+// region: -- PUT /admin/users/:username/password
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: change user password",
+    skip(admin, database, configuration, audit_context, data),
+    fields(admin = %admin.user.username)
+)]
+pub async fn admin_change_user_password(
+    admin: Authorized<CanManageUsers>,
+    State(database): State<Database>,
+    State(configuration): State<Settings>,
+    audit_context: AuditContext,
+    Path(username): Path<String>,
+    Json(data): Json<PasswordData>,
+) -> Result<Response, UserError> {
+    change_password(
+        &username,
+        data.password,
+        &configuration.authentication,
+        &database.client,
+    )
+    .await?;
+
+    audit::record(
+        &database.client,
+        &audit_context,
+        AuditEntry::new("user.password_change", &admin.user.username).target(&username),
+    )
+    .await;
+
+    Ok(StatusCode::OK.into_response())
+}
+// endregion: -- PUT /admin/users/:username/password
+
+// region: -- PUT /admin/users/:username/role
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: set user role",
+    skip(admin, database, audit_context, data),
+    fields(admin = %admin.user.username, role = %data.role)
+)]
+pub async fn admin_set_user_role(
+    admin: Authorized<CanManageUsers>,
+    State(database): State<Database>,
+    audit_context: AuditContext,
+    Path(username): Path<String>,
+    Json(data): Json<RoleData>,
+) -> Result<Response, UserError> {
+    let previous = set_user_role(&username, data.role, &database.client).await?;
+
+    audit::record(
+        &database.client,
+        &audit_context,
+        AuditEntry::new("user.role_change", &admin.user.username)
+            .target(&username)
+            .diff(change("role", previous, data.role)),
+    )
+    .await;
+
+    Ok(StatusCode::OK.into_response())
+}
+// endregion: -- PUT /admin/users/:username/role
+
+// region: -- POST /admin/users/:username/deactivate
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: deactivate user",
+    skip(admin, database, session_store, audit_context),
+    fields(admin = %admin.user.username)
+)]
+pub async fn admin_deactivate_user(
+    admin: Authorized<CanManageUsers>,
+    State(database): State<Database>,
+    State(session_store): State<Arc<dyn SessionStore>>,
+    audit_context: AuditContext,
+    Path(username): Path<String>,
+) -> Result<Response, UserError> {
+    let user_id = deactivate_user(&username, &database.client).await?;
+    session_store
+        .delete_for_user(&user_id, None)
+        .await
+        .map_err(UserError::UnexpectedError)?;
+
+    audit::record(
+        &database.client,
+        &audit_context,
+        AuditEntry::new("user.deactivate", &admin.user.username)
+            .target(&username)
+            .diff(change("active", true, false)),
+    )
+    .await;
+
+    Ok(StatusCode::OK.into_response())
+}
+// endregion: -- POST /admin/users/:username/deactivate
@@ -1,11 +1,133 @@
-use axum::response::IntoResponse;
+use std::{future::Future, sync::Arc, time::Instant};
+
+use axum::{extract::State, response::IntoResponse, Json};
 use hyper::StatusCode;
+use serde::Serialize;
 use tracing::info;
 
+use crate::{configuration::Settings, db::Database, email_client::EmailClient};
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const GIT_SHA: &str = env!("GIT_SHA");
+
 // region: -- Health Check Handler
+/// Always succeeds while the server is up
+#[utoipa::path(
+    get,
+    path = "/health_check",
+    tag = "health",
+    responses((status = 200, description = "The server is up"))
+)]
 pub async fn handler_health_check() -> impl IntoResponse {
     info!("{:<8} - handler_health_check", "HANDLER");
 
     StatusCode::OK
 }
 // endregion: -- Health Check Handler
+
+// region: -- Liveness
+/// The process is up and serving requests; dependencies are not checked.
+pub async fn handler_liveness() -> impl IntoResponse {
+    Json(serde_json::json!({
+        "status": "ok",
+        "version": VERSION,
+        "git_sha": GIT_SHA,
+    }))
+}
+// endregion: -- Liveness
+
+// region: -- Readiness
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ProbeStatus {
+    Ok,
+    Unavailable,
+}
+
+#[derive(Serialize)]
+struct Probe {
+    status: ProbeStatus,
+    latency_ms: u128,
+}
+
+#[derive(Serialize)]
+struct Checks {
+    database: Probe,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<Probe>,
+}
+
+#[derive(Serialize)]
+struct Readiness {
+    status: ProbeStatus,
+    version: &'static str,
+    git_sha: &'static str,
+    checks: Checks,
+}
+
+/// Probes every dependency, each bounded by `health.timeout_milliseconds`.
+/// Responds 503 when any of them is unavailable. Failure details are only
+/// logged, since the endpoint is unauthenticated.
+#[tracing::instrument(name = "Readiness check", skip_all)]
+pub async fn handler_readiness(
+    State(configuration): State<Settings>,
+    State(database): State<Database>,
+    State(email_client): State<Arc<EmailClient>>,
+) -> impl IntoResponse {
+    let timeout = configuration.health.timeout();
+
+    let database = probe(timeout, async {
+        database.client.query("INFO FOR DB").await?.check()?;
+        Ok::<_, surrealdb::Error>(())
+    });
+    let email = async {
+        match configuration.health.probe_email {
+            true => Some(probe(timeout, email_client.probe()).await),
+            false => None,
+        }
+    };
+    let (database, email) = tokio::join!(database, email);
+
+    let ready = matches!(database.status, ProbeStatus::Ok)
+        && email
+            .as_ref()
+            .map_or(true, |email| matches!(email.status, ProbeStatus::Ok));
+    let (code, status) = match ready {
+        true => (StatusCode::OK, ProbeStatus::Ok),
+        false => (StatusCode::SERVICE_UNAVAILABLE, ProbeStatus::Unavailable),
+    };
+
+    (
+        code,
+        Json(Readiness {
+            status,
+            version: VERSION,
+            git_sha: GIT_SHA,
+            checks: Checks { database, email },
+        }),
+    )
+}
+
+async fn probe<E: std::fmt::Display>(
+    timeout: std::time::Duration,
+    check: impl Future<Output = Result<(), E>>,
+) -> Probe {
+    let started = Instant::now();
+    let error = match tokio::time::timeout(timeout, check).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("Timed out after {}ms", timeout.as_millis())),
+    };
+    if let Some(error) = &error {
+        tracing::warn!(%error, "Readiness probe failed");
+    }
+
+    Probe {
+        status: match error {
+            None => ProbeStatus::Ok,
+            Some(_) => ProbeStatus::Unavailable,
+        },
+        latency_ms: started.elapsed().as_millis(),
+    }
+}
+// endregion: -- Readiness
@@ -1,11 +1,54 @@
-use axum::{body::Full, response::Response};
+use askama::Template;
+use axum::response::{Html, IntoResponse, Response};
 use axum_macros::debug_handler;
-use hyper::body::Bytes;
+use color_eyre::eyre::Context;
+
+use crate::{csrf::CsrfToken, domain::FieldError, error::SubscribeError};
+
+#[derive(Template, Default)]
+#[template(path = "home.html")]
+pub struct HomeTemplate {
+    pub csrf_token: String,
+    /// What the visitor typed, kept when the form is shown again.
+    pub name: String,
+    pub email: String,
+    pub name_errors: Vec<String>,
+    pub email_errors: Vec<String>,
+}
+
+impl HomeTemplate {
+    /// The subscribe form again, with every rejected field explained.
+    pub fn with_errors(
+        csrf_token: String,
+        name: String,
+        email: String,
+        errors: &[FieldError],
+    ) -> Self {
+        let messages = |field: &str| {
+            errors
+                .iter()
+                .filter(|e| e.field == field)
+                .map(|e| e.message.clone())
+                .collect()
+        };
+        Self {
+            csrf_token,
+            name,
+            email,
+            name_errors: messages("name"),
+            email_errors: messages("email"),
+        }
+    }
+}
 
 #[debug_handler]
-pub async fn home() -> Response<Full<Bytes>> {
-    Response::builder()
-        .header("Content-Type", "text/html; charset=utf-8")
-        .body(Full::from(include_str!("home.html")))
-        .unwrap()
+pub async fn home(csrf: CsrfToken) -> Result<Response, SubscribeError> {
+    let html = HomeTemplate {
+        csrf_token: csrf.to_string(),
+        ..Default::default()
+    }
+    .render()
+    .context("Failed to render the home page")?;
+
+    Ok(Html(html).into_response())
 }
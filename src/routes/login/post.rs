@@ -1,14 +1,19 @@
 use axum::{extract::State, response::Response, Form};
 use axum_macros::debug_handler;
 use hyper::{Body, StatusCode};
-use secrecy::{ExposeSecret, Secret};
-use tower_cookies::{Cookie, Cookies, Key};
+use secrecy::Secret;
 
 use crate::{
-    authentication::{validate_credentials, Credentials},
+    audit::{self, AuditContext, AuditEntry},
+    authentication::{
+        get_totp_secret, validate_credentials_throttled, ClientIp, Credentials, LoginThrottle,
+    },
+    configuration::Settings,
     db::Database,
     error::{AuthError, LoginError},
-    startup::{AppState, HmacSecret},
+    flash::Flash,
+    session::Session,
+    startup::AppState,
 };
 
 #[derive(serde::Deserialize)]
@@ -17,39 +22,89 @@ pub struct FormData {
     password: Secret<String>,
 }
 
+#[allow(clippy::too_many_arguments)]
 #[debug_handler(state = AppState)]
-#[tracing::instrument(name = "Login", skip(form, cookies, database), fields(
+#[tracing::instrument(name = "Login", skip(form, flash, session, database, configuration, throttle, audit_context), fields(
     username = tracing::field::Empty,
     user_id = tracing::field::Empty,
 ))]
 pub async fn login(
     State(database): State<Database>,
-    State(secret): State<HmacSecret>,
-    cookies: Cookies,
+    State(configuration): State<Settings>,
+    State(throttle): State<LoginThrottle>,
+    client_ip: ClientIp,
+    audit_context: AuditContext,
+    mut flash: Flash,
+    mut session: Session,
     Form(form): Form<FormData>,
 ) -> Result<Response, LoginError> {
     let credentials = Credentials {
-        username: form.username,
+        username: form.username.clone(),
         password: form.password,
     };
 
     tracing::Span::current().record("username", &tracing::field::display(&credentials.username));
-    match validate_credentials(credentials, &database.client).await {
+    match validate_credentials_throttled(
+        credentials,
+        client_ip,
+        &throttle,
+        &configuration.authentication.argon2,
+        &database.client,
+        &audit_context,
+    )
+    .await
+    {
         Ok(user_id) => {
             tracing::Span::current().record("user_id", &tracing::field::display(&user_id.id));
+            let has_second_factor = get_totp_secret(&user_id, &database.client)
+                .await
+                .map_err(LoginError::UnexpectedError)?
+                .is_some();
+
+            let action = if has_second_factor {
+                "login.password_verified"
+            } else {
+                "login.success"
+            };
+            audit::record(
+                &database.client,
+                &audit_context,
+                AuditEntry::new(action, &form.username),
+            )
+            .await;
+
+            session.renew().await.map_err(LoginError::UnexpectedError)?;
+            let location = if has_second_factor {
+                session
+                    .insert_pending_second_factor(user_id, form.username)
+                    .await
+                    .map_err(LoginError::UnexpectedError)?;
+                "/login/two-factor"
+            } else {
+                session
+                    .insert_user(user_id, form.username)
+                    .await
+                    .map_err(LoginError::UnexpectedError)?;
+                "/admin/dashboard"
+            };
             Ok(Response::builder()
                 .status(StatusCode::SEE_OTHER)
-                .header("Location", "/")
+                .header("Location", location)
                 .body(axum::body::boxed(Body::empty()))
                 .map_err(|e| LoginError::UnexpectedError(e.into()))?)
         }
         Err(e) => match e {
             AuthError::UnexpectedError(_) => Err(LoginError::UnexpectedError(e.into())),
             AuthError::InvalidCredentials(_) => {
+                audit::record(
+                    &database.client,
+                    &audit_context,
+                    AuditEntry::new("login.failure", &form.username),
+                )
+                .await;
+
                 let err = LoginError::AuthError(e.into());
-                let key = Key::from(secret.0.expose_secret().as_bytes());
-                let private_cookies = cookies.private(&key);
-                private_cookies.add(Cookie::new("_flash", err.to_string()));
+                flash.error(err.to_string());
 
                 tracing::warn!(err = ?err, "Invalid credentials");
                 Ok(Response::builder()
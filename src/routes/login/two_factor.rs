@@ -0,0 +1,134 @@
+use askama::Template;
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse, Redirect, Response},
+    Form,
+};
+use axum_macros::debug_handler;
+use color_eyre::eyre::{eyre, Context};
+use secrecy::Secret;
+
+use crate::{
+    audit::{self, AuditContext, AuditEntry},
+    authentication::{record_login_failure, verify_second_factor, ClientIp, LoginThrottle},
+    csrf::CsrfToken,
+    db::Database,
+    error::{AuthError, LoginError},
+    flash::{Flash, FlashMessage},
+    security_headers::CspNonce,
+    session::Session,
+    startup::AppState,
+};
+
+#[derive(serde::Deserialize)]
+pub struct TwoFactorFormData {
+    code: Secret<String>,
+}
+
+#[derive(Template)]
+#[template(path = "login_two_factor.html")]
+struct TwoFactorTemplate {
+    flash: Vec<FlashMessage>,
+    csrf_token: String,
+    csp_nonce: String,
+}
+
+// region: -- GET /login/two-factor
+#[debug_handler(state = AppState)]
+pub async fn login_two_factor_form(
+    session: Session,
+    mut flash: Flash,
+    csrf: CsrfToken,
+    nonce: CspNonce,
+) -> Result<Response, LoginError> {
+    if session.pending_second_factor().is_none() {
+        return Ok(Redirect::to("/login").into_response());
+    }
+
+    let html = TwoFactorTemplate {
+        flash: flash.take(),
+        csrf_token: csrf.to_string(),
+        csp_nonce: nonce.to_string(),
+    }
+    .render()
+    .context("Failed to render the two-factor form")?;
+
+    Ok(Html(html).into_response())
+}
+// endregion: -- GET /login/two-factor
+
+// region: -- POST /login/two-factor
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Login: second factor",
+    skip(form, flash, session, database, throttle, audit_context),
+    fields(username = tracing::field::Empty)
+)]
+pub async fn login_two_factor(
+    State(database): State<Database>,
+    State(throttle): State<LoginThrottle>,
+    ClientIp(client_ip): ClientIp,
+    audit_context: AuditContext,
+    mut flash: Flash,
+    mut session: Session,
+    Form(form): Form<TwoFactorFormData>,
+) -> Result<Response, LoginError> {
+    let Some(pending) = session.pending_second_factor().cloned() else {
+        return Ok(Redirect::to("/login").into_response());
+    };
+    tracing::Span::current().record("username", &tracing::field::display(&pending.username));
+
+    let result = match throttle.check(&pending.username, client_ip) {
+        Some(_) => Err(AuthError::InvalidCredentials(eyre!("Locked out."))),
+        None => {
+            verify_second_factor(
+                &pending.user_id,
+                &pending.username,
+                form.code,
+                &database.client,
+            )
+            .await
+        }
+    };
+
+    match result {
+        Ok(()) => {
+            throttle.record_success(&pending.username);
+            audit::record(
+                &database.client,
+                &audit_context,
+                AuditEntry::new("login.success", &pending.username),
+            )
+            .await;
+            session.renew().await.map_err(LoginError::UnexpectedError)?;
+            session
+                .insert_user(pending.user_id, pending.username)
+                .await
+                .map_err(LoginError::UnexpectedError)?;
+            Ok(Redirect::to("/admin/dashboard").into_response())
+        }
+        Err(AuthError::InvalidCredentials(e)) => {
+            record_login_failure(
+                &throttle,
+                &pending.username,
+                client_ip,
+                &database.client,
+                &audit_context,
+            )
+            .await;
+            audit::record(
+                &database.client,
+                &audit_context,
+                AuditEntry::new("login.second_factor_failure", &pending.username),
+            )
+            .await;
+            let err = LoginError::AuthError(e);
+            flash.error(err.to_string());
+
+            tracing::warn!(err = ?err, "Invalid second factor");
+            Ok(Redirect::to("/login/two-factor").into_response())
+        }
+        Err(e) => Err(LoginError::UnexpectedError(e.into())),
+    }
+}
+// endregion: -- POST /login/two-factor
@@ -0,0 +1,17 @@
+use axum::response::{IntoResponse, Redirect, Response};
+use axum_macros::debug_handler;
+
+use crate::{error::LoginError, session::Session, startup::AppState};
+
+// region: -- Logout Handler
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Logout", skip(session), fields(username = ?session.username()))]
+pub async fn logout(session: Session) -> Result<Response, LoginError> {
+    session
+        .log_out()
+        .await
+        .map_err(LoginError::UnexpectedError)?;
+
+    Ok(Redirect::to("/login").into_response())
+}
+// endregion: -- Logout Handler
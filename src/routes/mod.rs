@@ -1,13 +1,19 @@
+mod admin;
 mod health_check;
 mod home;
 mod login;
+mod logout;
 mod newsletters;
+mod subscribers;
 mod subscriptions;
 mod subscriptions_confirm;
 
+pub use admin::*;
 pub use health_check::*;
 pub use home::*;
 pub use login::*;
+pub use logout::*;
 pub use newsletters::*;
+pub use subscribers::*;
 pub use subscriptions::*;
 pub use subscriptions_confirm::*;
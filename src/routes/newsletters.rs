@@ -4,78 +4,203 @@ use axum::{
     Json,
 };
 use axum_macros::debug_handler;
-use base64::Engine;
 use color_eyre::eyre::Context;
 use hyper::{HeaderMap, StatusCode};
-use secrecy::Secret;
 use serde::Deserialize;
 use std::sync::Arc;
 use surrealdb::{engine::remote::ws::Client, Surreal};
+use utoipa::ToSchema;
 
-#[allow(unused_imports)]
 use crate::{
-    authentication::validate_credentials, db::Database, domain::SubscriberEmail,
-    email_client::EmailClient, error::PublishError, startup::AppState,
-    telemetry::spawn_block_with_tracing,
+    audit::{self, AuditContext, AuditEntry},
+    authentication::{
+        authenticate_api_token, basic_authentication, bearer_token, get_totp_secret, get_user_role,
+        require_role, validate_credentials_throttled, ApiTokenScope, ClientIp, LoginThrottle, Role,
+    },
+    configuration::Settings,
+    db::Database,
+    domain::SubscriberEmail,
+    email_client::EmailClient,
+    error::{AuthError, PublishError},
+    problem::Problem,
+    prometheus::{time_query, DeliveryQueue},
+    startup::AppState,
 };
-use crate::{authentication::Credentials, error::AuthError};
 
-#[derive(Deserialize)]
+/// A newsletter issue to send to every confirmed subscriber.
+#[derive(Deserialize, ToSchema)]
 pub struct BodyData {
     title: String,
     content: Content,
 }
 
-#[derive(Deserialize)]
+/// The same issue as plain text and as HTML.
+#[derive(Deserialize, ToSchema)]
 pub struct Content {
     text: String,
     html: String,
 }
 
 // region: -- /newsletters handler
+/// Publish a newsletter issue
+#[utoipa::path(
+    post,
+    path = "/newsletters",
+    tag = "newsletters",
+    request_body = BodyData,
+    security(("basic" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "The issue was delivered"),
+        (status = 401, description = "Missing or invalid credentials", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Editors and owners only", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected failure", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[allow(clippy::too_many_arguments)]
 #[debug_handler(state = AppState)]
 #[tracing::instrument(
     name = "Publishing a newsletter",
-    skip(database, email_client, headers, body),
+    skip(database, configuration, email_client, throttle, audit_context, headers, body),
     fields(
         username = tracing::field::Empty,
         user_id = tracing::field::Empty,
     )
-
 )]
 pub async fn publish_newsletter(
     State(database): State<Database>,
+    State(configuration): State<Settings>,
     State(email_client): State<Arc<EmailClient>>,
+    State(throttle): State<LoginThrottle>,
+    client_ip: ClientIp,
+    audit_context: AuditContext,
     headers: HeaderMap,
     body: Json<BodyData>,
 ) -> Result<Response, PublishError> {
-    let credentials = basic_authentication(&headers).map_err(PublishError::AuthError)?;
-    tracing::Span::current().record("username", &tracing::field::display(&credentials.username));
-    let user_id = validate_credentials(credentials, &database.client)
-        .await
-        .map_err(|e| match e {
-            AuthError::InvalidCredentials(_) => PublishError::AuthError(e.into()),
-            AuthError::UnexpectedError(_) => PublishError::UnexpectedError(e.into()),
-        })?;
-    tracing::Span::current().record("user_id", &tracing::field::display(&user_id.id));
+    let username = match bearer_token(&headers) {
+        Some(token) => {
+            let owner = authenticate_api_token(&token, ApiTokenScope::Publish, &database.client)
+                .await
+                .map_err(into_publish_error)?;
+            tracing::Span::current().record("user_id", &tracing::field::display(&owner.user_id.id));
+            require_role(owner.role, Role::Editor)?;
+            owner.username
+        }
+        None => {
+            let credentials = basic_authentication(&headers).map_err(PublishError::AuthError)?;
+            let username = credentials.username.clone();
+            let result = validate_credentials_throttled(
+                credentials,
+                client_ip,
+                &throttle,
+                &configuration.authentication.argon2,
+                &database.client,
+                &audit_context,
+            )
+            .await;
+            if let Err(AuthError::InvalidCredentials(_)) = &result {
+                audit::record(
+                    &database.client,
+                    &audit_context,
+                    AuditEntry::new("login.failure", &username)
+                        .diff(serde_json::json!({ "via": "basic" })),
+                )
+                .await;
+            }
+            let user_id = result.map_err(into_publish_error)?;
+            tracing::Span::current().record("user_id", &tracing::field::display(&user_id.id));
+            // A password alone must not stand in for the second factor
+            if get_totp_secret(&user_id, &database.client).await?.is_some() {
+                return Err(PublishError::SecondFactorRequired);
+            }
+            require_role(
+                get_user_role(&user_id, &database.client).await?,
+                Role::Editor,
+            )?;
+            username
+        }
+    };
+    tracing::Span::current().record("username", &tracing::field::display(&username));
+
+    let recipients = publish_issue(
+        &database,
+        &email_client,
+        &NewsletterIssue {
+            title: &body.title,
+            html_content: &body.content.html,
+            text_content: &body.content.text,
+        },
+        &username,
+    )
+    .await?;
+
+    audit::record(
+        &database.client,
+        &audit_context,
+        AuditEntry::new("newsletter.publish", &username)
+            .target(&body.title)
+            .diff(serde_json::json!({ "recipients": recipients, "via": "api" })),
+    )
+    .await;
+
+    Ok(StatusCode::OK.into_response())
+}
+
+fn into_publish_error(e: AuthError) -> PublishError {
+    match e {
+        AuthError::InvalidCredentials(_) => PublishError::AuthError(e.into()),
+        AuthError::UnexpectedError(_) => PublishError::UnexpectedError(e.into()),
+    }
+}
+// endregion: -- /newsletters handler
 
-    let subscribers = get_confirmed_subscribers(database.client).await?;
+#[derive(Deserialize)]
+struct ConfirmedSubscriber {
+    email: SubscriberEmail,
+}
+
+// region: -- Publish Issue
+pub struct NewsletterIssue<'a> {
+    pub title: &'a str,
+    pub html_content: &'a str,
+    pub text_content: &'a str,
+}
+
+/// Delivers `issue` to every confirmed subscriber and records it in the issue
+/// history. Returns the number of subscribers the issue was sent to.
+#[tracing::instrument(
+    name = "Publishing a newsletter issue",
+    skip(database, email_client, issue),
+    fields(title = %issue.title)
+)]
+pub async fn publish_issue(
+    database: &Database,
+    email_client: &EmailClient,
+    issue: &NewsletterIssue<'_>,
+    published_by: &str,
+) -> color_eyre::Result<usize> {
+    let mut recipients = 0;
+
+    let subscribers = get_confirmed_subscribers(database.client.clone()).await?;
+    let mut queue = DeliveryQueue::new(subscribers.len());
     for subscriber in subscribers {
         match subscriber {
             Ok(subscriber) => {
                 email_client
                     .send_email(
                         &subscriber.email,
-                        &body.title,
-                        &body.content.html,
-                        &body.content.text,
+                        issue.title,
+                        issue.html_content,
+                        issue.text_content,
                     )
                     .await
                     .wrap_err_with(|| {
                         color_eyre::eyre::eyre!("Failed to send newsletter to {}", subscriber.email)
                     })?;
+                queue.pop();
+                recipients += 1;
             }
             Err(e) => {
+                queue.pop();
                 tracing::warn!(
                     e.cause_chain = ?e,
                     "Skipping a confirmed subscriber. \
@@ -84,53 +209,40 @@ pub async fn publish_newsletter(
             }
         }
     }
-    Ok(StatusCode::OK.into_response())
-}
-// endregion: -- /newsletters handler
 
-// region: -- Basic Authentication
-#[tracing::instrument(name = "Basic Authentication", skip(headers))]
-fn basic_authentication(headers: &HeaderMap) -> color_eyre::Result<Credentials> {
-    let header_value = headers
-        .get("Authorization")
-        .ok_or_else(|| color_eyre::eyre::eyre!("Missing authorization header"))?
-        .to_str()
-        .context("Authorization header was not valid UTF8")?;
-
-    let base64encoded_credentials = header_value
-        .strip_prefix("Basic ")
-        .ok_or_else(|| color_eyre::eyre::eyre!("Authorization header did not start with Basic"))?;
-
-    let decoded_credentials = base64::engine::general_purpose::STANDARD
-        .decode(base64encoded_credentials)
-        .map_err(|e| color_eyre::eyre::eyre!("Failed to base64-decode credentials: {}", e))?;
-
-    let decoded_credentials = String::from_utf8(decoded_credentials)
-        .map_err(|e| color_eyre::eyre::eyre!("Credentials were not valid UTF8: {}", e))?;
-
-    let mut credentials = decoded_credentials.splitn(2, ':');
-
-    let username = credentials
-        .next()
-        .ok_or_else(|| color_eyre::eyre::eyre!("A username must be provided in 'Basic' auth."))?
-        .to_owned();
-
-    let password = credentials
-        .next()
-        .ok_or_else(|| color_eyre::eyre::eyre!("A password must be provided in 'Basic' auth."))?
-        .to_owned();
-
-    Ok(Credentials {
-        username,
-        password: Secret::new(password),
-    })
+    store_issue(&database.client, issue, published_by, recipients)
+        .await
+        .context("Failed to store the published issue.")?;
+
+    Ok(recipients)
 }
-// endregion: -- Basic Authentication
 
-#[derive(Deserialize)]
-struct ConfirmedSubscriber {
-    email: SubscriberEmail,
+#[tracing::instrument(name = "Storing newsletter issue", skip(conn, issue))]
+async fn store_issue(
+    conn: &Surreal<Client>,
+    issue: &NewsletterIssue<'_>,
+    published_by: &str,
+    recipients: usize,
+) -> Result<(), surrealdb::Error> {
+    let sql = "CREATE newsletter_issues SET title = $title, text_content = $text_content, \
+        html_content = $html_content, published_by = $published_by, \
+        recipients = $recipients, published_at = time::now()";
+
+    time_query(
+        "newsletter_issue.insert",
+        conn.query(sql)
+            .bind(("title", issue.title))
+            .bind(("text_content", issue.text_content))
+            .bind(("html_content", issue.html_content))
+            .bind(("published_by", published_by))
+            .bind(("recipients", recipients)),
+    )
+    .await?
+    .check()?;
+
+    Ok(())
 }
+// endregion: -- Publish Issue
 
 // region: -- Get Confirmed Subscribers
 #[tracing::instrument(name = "Getting Confirmed Subscribers", skip(conn))]
@@ -144,7 +256,9 @@ async fn get_confirmed_subscribers(
 
     let sql = "SELECT email FROM subscriptions WHERE status = 'confirmed'";
 
-    let mut res = conn.query(sql).await?.check()?;
+    let mut res = time_query("subscriber.confirmed", conn.query(sql))
+        .await?
+        .check()?;
     let subscribers: Vec<Subscriber> = res.take(0)?;
 
     let confirmed_subscribers = subscribers
@@ -0,0 +1,73 @@
+use axum::{extract::State, Json};
+use axum_macros::debug_handler;
+use hyper::HeaderMap;
+use serde::{Deserialize, Serialize};
+use surrealdb::{engine::remote::ws::Client, Surreal};
+use utoipa::ToSchema;
+
+use crate::{
+    authentication::{authenticate_api_token, bearer_token, ApiTokenScope},
+    db::Database,
+    error::{ApiTokenError, AuthError},
+    problem::Problem,
+    startup::AppState,
+};
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct SubscriberSummary {
+    pub email: String,
+    pub name: String,
+    pub status: String,
+    pub subscribed_at: String,
+}
+
+// region: -- GET /subscribers
+/// List every subscriber
+#[utoipa::path(
+    get,
+    path = "/subscribers",
+    tag = "subscribers",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Subscribers, oldest first", body = Vec<SubscriberSummary>),
+        (status = 401, description = "Missing or invalid token, or the token lacks `subscribers:read`", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected failure", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Listing subscribers",
+    skip(database, headers),
+    fields(username = tracing::field::Empty)
+)]
+pub async fn list_subscribers(
+    State(database): State<Database>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<SubscriberSummary>>, ApiTokenError> {
+    let token = bearer_token(&headers)
+        .ok_or_else(|| ApiTokenError::AuthError(color_eyre::eyre::eyre!("Missing bearer token")))?;
+    let owner = authenticate_api_token(&token, ApiTokenScope::SubscribersRead, &database.client)
+        .await
+        .map_err(|e| match e {
+            AuthError::InvalidCredentials(_) => ApiTokenError::AuthError(e.into()),
+            AuthError::UnexpectedError(_) => ApiTokenError::UnexpectedError(e.into()),
+        })?;
+    tracing::Span::current().record("username", &tracing::field::display(&owner.username));
+
+    Ok(Json(get_subscribers(&database.client).await?))
+}
+// endregion: -- GET /subscribers
+
+// region: -- Get Subscribers (SurrealDB Retrieve)
+#[tracing::instrument(name = "Retrieve subscribers", skip(conn))]
+pub async fn get_subscribers(
+    conn: &Surreal<Client>,
+) -> Result<Vec<SubscriberSummary>, surrealdb::Error> {
+    let sql = "SELECT email, name, status, <string> subscribed_at AS subscribed_at \
+        FROM subscriptions ORDER BY subscribed_at";
+
+    let mut res = conn.query(sql).await?.check()?;
+    let subscribers: Vec<SubscriberSummary> = res.take(0)?;
+    Ok(subscribers)
+}
+// endregion: -- Get Subscribers (SurrealDB Retrieve)
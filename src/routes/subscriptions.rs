@@ -1,16 +1,23 @@
 #[allow(unused_imports)]
 use crate::{
+    csrf::CsrfToken,
     db::{Database, Transaction},
-    domain::{NewSubscriber, SubscriberEmail, SubscriberName},
+    domain::{FieldError, NewSubscriber, SubscriberEmail, SubscriberName},
     email_client::EmailClient,
     error::{StoreTokenError, SubscribeError},
+    problem::{prefers_html, Problem},
+    prometheus::{self, time_query},
+    routes::HomeTemplate,
     startup::{AppState, ApplicationBaseUrl},
 };
+use askama::Template;
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::{IntoResponse, Response},
-    Form,
+    async_trait,
+    body::Body,
+    extract::{FromRequest, State},
+    http::{header, HeaderMap, Request, StatusCode},
+    response::{Html, IntoResponse, Response},
+    Form, Json,
 };
 use axum_macros::debug_handler;
 use color_eyre::eyre::Context;
@@ -23,26 +30,26 @@ use surrealdb::{
     sql::{self, Thing},
     Surreal,
 };
+use utoipa::ToSchema;
 
-#[derive(Deserialize, Debug)]
+/// A subscribe request, sent as a form or as JSON.
+#[derive(Deserialize, Debug, Clone, ToSchema)]
 pub struct FormData {
     pub email: String,
     pub name: String,
 }
 
-pub fn parse_subscriber(Form(data): Form<FormData>) -> std::result::Result<NewSubscriber, String> {
-    let name = SubscriberName::parse(data.name)?;
-    let email = SubscriberEmail::parse(data.email)?;
-    Ok(NewSubscriber { email, name })
+pub fn parse_subscriber(
+    Form(data): Form<FormData>,
+) -> std::result::Result<NewSubscriber, Vec<FieldError>> {
+    data.try_into()
 }
 
 impl TryFrom<FormData> for NewSubscriber {
-    type Error = String;
+    type Error = Vec<FieldError>;
 
     fn try_from(value: FormData) -> std::result::Result<Self, Self::Error> {
-        let name = SubscriberName::parse(value.name)?;
-        let email = SubscriberEmail::parse(value.email)?;
-        Ok(Self { email, name })
+        NewSubscriber::parse(value.name, value.email)
     }
 }
 
@@ -55,26 +62,159 @@ fn generate_subscription_token() -> String {
         .collect()
 }
 
+// region: -- Subscribe Request
+/// The subscribe payload, read as JSON or as a form depending on `Content-Type`.
+pub struct SubscribeRequest {
+    pub data: FormData,
+    pub json: bool,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequest<S, Body> for SubscribeRequest {
+    type Rejection = Response;
+
+    async fn from_request(request: Request<Body>, state: &S) -> Result<Self, Self::Rejection> {
+        let json = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map_or(false, |mime| {
+                mime.trim().eq_ignore_ascii_case("application/json")
+            });
+
+        let data = match json {
+            true => {
+                Json::<FormData>::from_request(request, state)
+                    .await
+                    .map_err(IntoResponse::into_response)?
+                    .0
+            }
+            false => {
+                Form::<FormData>::from_request(request, state)
+                    .await
+                    .map_err(IntoResponse::into_response)?
+                    .0
+            }
+        };
+        Ok(Self { data, json })
+    }
+}
+
+/// What a subscribe request did. Only JSON clients are told: form clients
+/// always see the same response, so the form can't be used to probe who
+/// is subscribed.
+#[derive(Clone, Copy, Debug)]
+pub enum SubscribeOutcome {
+    Pending,
+    Resent,
+    AlreadyConfirmed,
+}
+
+impl SubscribeOutcome {
+    fn status_code(self) -> StatusCode {
+        match self {
+            Self::Pending => StatusCode::CREATED,
+            Self::Resent => StatusCode::ACCEPTED,
+            Self::AlreadyConfirmed => StatusCode::OK,
+        }
+    }
+
+    fn into_json(self) -> Response {
+        let (status, confirmation_email_sent) = match self {
+            Self::Pending | Self::Resent => ("pending_confirmation", true),
+            Self::AlreadyConfirmed => ("confirmed", false),
+        };
+        let body = SubscribeResponse {
+            status,
+            confirmation_email_sent,
+        };
+        (self.status_code(), Json(body)).into_response()
+    }
+}
+
+/// What JSON clients are told about their subscription.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct SubscribeResponse {
+    /// `pending_confirmation` or `confirmed`
+    pub status: &'static str,
+    pub confirmation_email_sent: bool,
+}
+// endregion: -- Subscribe Request
+
 // region: -- Subscribe Handler
+/// Subscribe to the newsletter
+#[utoipa::path(
+    post,
+    path = "/subscribe",
+    tag = "subscriptions",
+    request_body(content(
+        (FormData = "application/x-www-form-urlencoded"),
+        (FormData = "application/json"),
+    )),
+    responses(
+        (status = 200, description = "Form clients, whatever the outcome; JSON clients whose address is already confirmed", body = SubscribeResponse),
+        (status = 201, description = "A new subscriber is pending confirmation", body = SubscribeResponse),
+        (status = 202, description = "The subscriber was already pending; the confirmation email was sent again", body = SubscribeResponse),
+        (status = 400, description = "Invalid name or email", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "A field is missing", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected failure", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 #[debug_handler(state = AppState)]
 #[tracing::instrument(
     name = "Adding a new subscriber.",
-    skip(data, email_client, base_url, database),
+    skip(request, email_client, base_url, database, csrf, headers),
     fields(
-        subscriber_email = %data.email,
-        subscriber_name = %data.name,
+        subscriber_email = %request.data.email,
+        subscriber_name = %request.data.name,
     )
 )]
 pub async fn handler_subscribe(
     State(email_client): State<Arc<EmailClient>>,
     State(base_url): State<ApplicationBaseUrl>,
     State(database): State<Database>,
-    Form(data): Form<FormData>,
+    csrf: CsrfToken,
+    headers: HeaderMap,
+    request: SubscribeRequest,
 ) -> Result<Response, SubscribeError> {
-    let new_subscriber: NewSubscriber = Form(data)
-        .0
-        .try_into()
-        .map_err(SubscribeError::ValidationError)?;
+    let SubscribeRequest { data, json } = request;
+    let new_subscriber: NewSubscriber = match data.clone().try_into() {
+        Ok(new_subscriber) => new_subscriber,
+        // Browsers get the form back with the problems next to the fields
+        Err(errors) if !json && prefers_html(&headers) => {
+            let html = HomeTemplate::with_errors(csrf.to_string(), data.name, data.email, &errors)
+                .render()
+                .context("Failed to render the subscribe form")?;
+            return Ok((StatusCode::BAD_REQUEST, Html(html)).into_response());
+        }
+        Err(errors) => return Err(SubscribeError::ValidationError(errors)),
+    };
+
+    let outcome = subscribe(&new_subscriber, &email_client, &base_url, &database).await?;
+    tracing::info!(?outcome, "Subscribe request handled");
+
+    match json {
+        true => Ok(outcome.into_json()),
+        false => Ok(StatusCode::OK.into_response()),
+    }
+}
+
+/// Stores a new pending subscriber, or issues a fresh token to one still
+/// pending, and sends the confirmation email. Confirmed subscribers are left
+/// alone.
+async fn subscribe(
+    new_subscriber: &NewSubscriber,
+    email_client: &EmailClient,
+    base_url: &ApplicationBaseUrl,
+    database: &Database,
+) -> Result<SubscribeOutcome, SubscribeError> {
+    let existing = find_subscription(&new_subscriber.email, &database.client)
+        .await
+        .context("Failed to look up an existing subscription.")?;
+    if matches!(&existing, Some(existing) if existing.status == "confirmed") {
+        return Ok(SubscribeOutcome::AlreadyConfirmed);
+    }
 
     let transaction = Transaction::begin(&database.client)
         .await
@@ -82,9 +222,15 @@ pub async fn handler_subscribe(
 
     let conn = transaction.conn;
 
-    let subscriber_id = insert_subscriber(&new_subscriber, conn)
-        .await
-        .context("Failed to insert new seubscriber in the database.")?;
+    let (subscriber_id, outcome) = match existing {
+        Some(existing) => (existing.id, SubscribeOutcome::Resent),
+        None => {
+            let subscriber_id = insert_subscriber(new_subscriber, conn)
+                .await
+                .context("Failed to insert new seubscriber in the database.")?;
+            (subscriber_id, SubscribeOutcome::Pending)
+        }
+    };
 
     let subscription_token = generate_subscription_token();
 
@@ -96,20 +242,48 @@ pub async fn handler_subscribe(
         .commit()
         .await
         .context("Failed to commit transaction to store a new subscriber.")?;
+    if matches!(outcome, SubscribeOutcome::Pending) {
+        metrics::increment_counter!(prometheus::SUBSCRIBER_SIGNUPS_TOTAL);
+    }
 
     send_confirmation_email(
-        &email_client,
-        new_subscriber,
+        email_client,
+        new_subscriber.clone(),
         &base_url.0,
         &subscription_token,
     )
     .await
     .context("Failed to send a confirmation email to the new subscriber.")?;
 
-    Ok(StatusCode::OK.into_response())
+    Ok(outcome)
 }
 // endregion: -- Subscribe Handler
 
+// region: -- Find Subscription (SurrealDB Retrieve)
+#[derive(Deserialize, Debug)]
+pub struct ExistingSubscription {
+    pub id: Thing,
+    pub status: String,
+}
+
+#[tracing::instrument(name = "Looking up an existing subscription", skip(email, client))]
+pub async fn find_subscription(
+    email: &SubscriberEmail,
+    client: &Surreal<Client>,
+) -> Result<Option<ExistingSubscription>, surrealdb::Error> {
+    let sql = "SELECT id, status FROM subscriptions WHERE email = $email";
+
+    let mut res = time_query(
+        "subscriber.find",
+        client.query(sql).bind(("email", email.as_ref())),
+    )
+    .await?
+    .check()?;
+    let existing: Option<ExistingSubscription> = res.take(0)?;
+    Ok(existing)
+}
+// endregion: -- Find Subscription (SurrealDB Retrieve)
+
 // region: -- Insert Subscriber (SurrealDB Store)
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Subscription {
@@ -140,7 +314,10 @@ pub async fn insert_subscriber(
         "pending_confirmation"
     );
 
-    match client.query(query).await?.check() {
+    match time_query("subscriber.insert", client.query(query))
+        .await?
+        .check()
+    {
         Ok(_) => Ok(subscriber_id),
         Err(e) => Err(e),
     }
@@ -172,7 +349,9 @@ pub async fn store_token(
         subtoken_id, &subscription_token
     );
 
-    client.query(&query).await?.check()?;
+    time_query("subscription_token.insert", client.query(&query))
+        .await?
+        .check()?;
 
     // Associate the subscription token with the subscriber
     let query = format!(
@@ -182,7 +361,9 @@ pub async fn store_token(
         Thing::from(("subscribes".into(), sql::Uuid::new_v4().to_string()))
     );
 
-    client.query(&query).await?.check()?;
+    time_query("subscription_token.relate", client.query(&query))
+        .await?
+        .check()?;
 
     Ok(())
 }
@@ -6,16 +6,37 @@ use axum::{
 use axum_macros::debug_handler;
 use color_eyre::eyre::Context;
 use surrealdb::sql::Thing;
+use utoipa::IntoParams;
 
 #[allow(unused_imports)]
-use crate::{db::Database, error::ConfirmationError, startup::AppState};
+use crate::{
+    db::Database,
+    error::ConfirmationError,
+    problem::Problem,
+    prometheus::{self, time_query},
+    startup::AppState,
+};
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct Parameters {
     subscription_token: String,
 }
 
 // region: -- Confirm Subscriber (HTTP Handler)
+/// Confirm a pending subscription
+#[utoipa::path(
+    get,
+    path = "/subscribe/confirm",
+    tag = "subscriptions",
+    params(Parameters),
+    responses(
+        (status = 200, description = "The subscription is confirmed"),
+        (status = 400, description = "The token is missing", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "The token is unknown", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected failure", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 #[debug_handler(state = AppState)]
 #[tracing::instrument(name = "Confirm a pending subscriber", skip(parameters, database))]
 pub async fn handler_confirm(
@@ -30,6 +51,7 @@ pub async fn handler_confirm(
     confirm_subscriber(&id, &database)
         .await
         .context("Failed to confirm the subscriber.")?;
+    metrics::increment_counter!(prometheus::SUBSCRIBER_CONFIRMATIONS_TOTAL);
 
     Ok(StatusCode::OK.into_response())
 }
@@ -45,11 +67,12 @@ pub async fn confirm_subscriber(
 
     let sql = "UPDATE subscriptions SET status = 'confirmed' WHERE id = $subscriber_id";
 
-    client
-        .query(sql)
-        .bind(("subscriber_id", subscriber_id))
-        .await?
-        .check()?;
+    time_query(
+        "subscriber.confirm",
+        client.query(sql).bind(("subscriber_id", subscriber_id)),
+    )
+    .await?
+    .check()?;
 
     Ok(())
 }
@@ -71,11 +94,14 @@ pub async fn get_subscriber_id_from_token(
         SELECT *, $token_id->subscribes->id from subscriptions;
     ";
 
-    let mut res = client
-        .query(sql)
-        .bind(("subscription_token", subscription_token))
-        .await?
-        .check()?;
+    let mut res = time_query(
+        "subscription_token.lookup",
+        client
+            .query(sql)
+            .bind(("subscription_token", subscription_token)),
+    )
+    .await?
+    .check()?;
 
     let subscriber_id: Option<Thing> = res.take((1, "id"))?;
 
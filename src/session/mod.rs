@@ -0,0 +1,241 @@
+mod redis;
+mod surreal;
+
+pub use self::redis::RedisSessionStore;
+pub use surreal::SurrealSessionStore;
+
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use futures_core::future::BoxFuture;
+use hyper::StatusCode;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+use tower_cookies::{cookie::SameSite, Cookie, Cookies, Key};
+
+use crate::{
+    configuration::{SessionBackend, Settings},
+    db::Database,
+    startup::{AppState, HmacSecret},
+};
+
+pub const SESSION_COOKIE: &str = "session_id";
+
+// region: -- Session Store
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SessionData {
+    pub user_id: Option<Thing>,
+    pub username: Option<String>,
+    /// Set between a correct password and a correct second factor.
+    #[serde(default)]
+    pub pending_second_factor: Option<PendingSecondFactor>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PendingSecondFactor {
+    pub user_id: Thing,
+    pub username: String,
+}
+
+pub trait SessionStore: Send + Sync {
+    fn load<'a>(
+        &'a self,
+        session_id: &'a str,
+    ) -> BoxFuture<'a, color_eyre::Result<Option<SessionData>>>;
+
+    fn store<'a>(
+        &'a self,
+        session_id: &'a str,
+        data: &'a SessionData,
+        ttl: Duration,
+    ) -> BoxFuture<'a, color_eyre::Result<()>>;
+
+    fn delete<'a>(&'a self, session_id: &'a str) -> BoxFuture<'a, color_eyre::Result<()>>;
+
+    /// Removes every session belonging to `user_id`, except `keep` if provided.
+    fn delete_for_user<'a>(
+        &'a self,
+        user_id: &'a Thing,
+        keep: Option<&'a str>,
+    ) -> BoxFuture<'a, color_eyre::Result<()>>;
+}
+
+#[tracing::instrument(name = "Building session store", skip(configuration, database))]
+pub async fn build_session_store(
+    configuration: &Settings,
+    database: &Database,
+) -> color_eyre::Result<Arc<dyn SessionStore>> {
+    match configuration.session.backend {
+        SessionBackend::Redis => {
+            let redis_uri = configuration.session.redis_uri.as_ref().ok_or_else(|| {
+                color_eyre::eyre::eyre!("session.redis_uri must be set for the redis backend")
+            })?;
+            let store = RedisSessionStore::new(redis_uri.expose_secret()).await?;
+            Ok(Arc::new(store))
+        }
+        SessionBackend::Surrealdb => Ok(Arc::new(SurrealSessionStore::new(database.clone()))),
+    }
+}
+
+fn generate_session_id() -> String {
+    let mut rng = thread_rng();
+    std::iter::repeat_with(|| rng.sample(Alphanumeric))
+        .map(char::from)
+        .take(48)
+        .collect()
+}
+// endregion: -- Session Store
+
+// region: -- Session Extractor
+pub struct Session {
+    cookies: Cookies,
+    key: Key,
+    store: Arc<dyn SessionStore>,
+    ttl: Duration,
+    id: Option<String>,
+    data: SessionData,
+}
+
+impl Session {
+    pub fn user_id(&self) -> Option<&Thing> {
+        self.data.user_id.as_ref()
+    }
+
+    pub fn username(&self) -> Option<&str> {
+        self.data.username.as_deref()
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Drops the current session id and issues a fresh one, keeping the data.
+    /// Must be called whenever the privilege level changes (e.g. on login).
+    #[tracing::instrument(name = "Renew session", skip(self))]
+    pub async fn renew(&mut self) -> color_eyre::Result<()> {
+        if let Some(old_id) = self.id.take() {
+            self.store.delete(&old_id).await?;
+        }
+        self.id = Some(generate_session_id());
+        self.save().await
+    }
+
+    #[tracing::instrument(name = "Insert user into session", skip(self, user_id, username))]
+    pub async fn insert_user(
+        &mut self,
+        user_id: Thing,
+        username: String,
+    ) -> color_eyre::Result<()> {
+        self.data.user_id = Some(user_id);
+        self.data.username = Some(username);
+        self.data.pending_second_factor = None;
+        self.save().await
+    }
+
+    pub fn pending_second_factor(&self) -> Option<&PendingSecondFactor> {
+        self.data.pending_second_factor.as_ref()
+    }
+
+    /// Remembers a user who has passed the password check but still owes a
+    /// second factor. They are not logged in until `insert_user` is called.
+    #[tracing::instrument(
+        name = "Insert pending second factor into session",
+        skip(self, user_id, username)
+    )]
+    pub async fn insert_pending_second_factor(
+        &mut self,
+        user_id: Thing,
+        username: String,
+    ) -> color_eyre::Result<()> {
+        self.data.user_id = None;
+        self.data.username = None;
+        self.data.pending_second_factor = Some(PendingSecondFactor { user_id, username });
+        self.save().await
+    }
+
+    #[tracing::instrument(name = "Log out session", skip(self))]
+    pub async fn log_out(mut self) -> color_eyre::Result<()> {
+        if let Some(id) = self.id.take() {
+            self.store.delete(&id).await?;
+        }
+        self.cookies
+            .private(&self.key)
+            .remove(Cookie::build(SESSION_COOKIE, "").path("/").finish());
+        Ok(())
+    }
+
+    async fn save(&mut self) -> color_eyre::Result<()> {
+        let id = match &self.id {
+            Some(id) => id.clone(),
+            None => {
+                let id = generate_session_id();
+                self.id = Some(id.clone());
+                id
+            }
+        };
+        self.store.store(&id, &self.data, self.ttl).await?;
+
+        let cookie = Cookie::build(SESSION_COOKIE, id)
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .max_age(tower_cookies::cookie::time::Duration::seconds(
+                self.ttl.as_secs() as i64,
+            ))
+            .finish();
+        self.cookies.private(&self.key).add(cookie);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Session {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let cookies = Cookies::from_request_parts(parts, state)
+            .await
+            .map_err(|e| e.into_response())?;
+
+        let HmacSecret(secret) = &state.secret;
+        let key = Key::from(secret.expose_secret().as_bytes());
+        let ttl = Duration::from_secs(state.configuration.session.ttl_seconds);
+
+        let id = cookies
+            .private(&key)
+            .get(SESSION_COOKIE)
+            .map(|c| c.value().to_string());
+
+        let (id, data) = match id {
+            Some(id) => match state.session_store.load(&id).await {
+                Ok(Some(data)) => (Some(id), data),
+                Ok(None) => (None, SessionData::default()),
+                Err(e) => {
+                    tracing::error!(error.cause_chain = ?e, "Failed to load session");
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+                }
+            },
+            None => (None, SessionData::default()),
+        };
+
+        Ok(Self {
+            cookies,
+            key,
+            store: state.session_store.clone(),
+            ttl,
+            id,
+            data,
+        })
+    }
+}
+// endregion: -- Session Extractor
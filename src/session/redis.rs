@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use color_eyre::eyre::Context;
+use futures_core::future::BoxFuture;
+use redis::{aio::ConnectionManager, AsyncCommands};
+use surrealdb::sql::Thing;
+
+use super::{SessionData, SessionStore};
+
+// region: -- Redis Session Store
+#[derive(Clone)]
+pub struct RedisSessionStore {
+    conn: ConnectionManager,
+}
+
+impl RedisSessionStore {
+    #[tracing::instrument(name = "Connecting to Redis", skip(redis_uri))]
+    pub async fn new(redis_uri: &str) -> color_eyre::Result<Self> {
+        let client = redis::Client::open(redis_uri).context("Invalid Redis URI")?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .context("Failed to connect to Redis")?;
+        Ok(Self { conn })
+    }
+
+    fn session_key(session_id: &str) -> String {
+        format!("session:{}", session_id)
+    }
+
+    fn user_key(user_id: &Thing) -> String {
+        format!("user_sessions:{}", user_id)
+    }
+}
+
+impl SessionStore for RedisSessionStore {
+    fn load<'a>(
+        &'a self,
+        session_id: &'a str,
+    ) -> BoxFuture<'a, color_eyre::Result<Option<SessionData>>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let value: Option<String> = conn.get(Self::session_key(session_id)).await?;
+            value
+                .map(|v| serde_json::from_str(&v).context("Failed to deserialize session"))
+                .transpose()
+        })
+    }
+
+    fn store<'a>(
+        &'a self,
+        session_id: &'a str,
+        data: &'a SessionData,
+        ttl: Duration,
+    ) -> BoxFuture<'a, color_eyre::Result<()>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let value = serde_json::to_string(data)?;
+            let _: () = conn
+                .set_ex(Self::session_key(session_id), value, ttl.as_secs() as usize)
+                .await?;
+
+            if let Some(user_id) = &data.user_id {
+                let user_key = Self::user_key(user_id);
+                let _: () = conn.sadd(&user_key, session_id).await?;
+                let _: () = conn.expire(&user_key, ttl.as_secs() as usize).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn delete<'a>(&'a self, session_id: &'a str) -> BoxFuture<'a, color_eyre::Result<()>> {
+        Box::pin(async move {
+            let user_id = self.load(session_id).await?.and_then(|data| data.user_id);
+            let mut conn = self.conn.clone();
+            let mut pipe = redis::pipe();
+            pipe.atomic().del(Self::session_key(session_id)).ignore();
+            // Keep the per-user index in step, or it outlives its sessions
+            if let Some(user_id) = &user_id {
+                pipe.srem(Self::user_key(user_id), session_id).ignore();
+            }
+            let _: () = pipe.query_async(&mut conn).await?;
+            Ok(())
+        })
+    }
+
+    fn delete_for_user<'a>(
+        &'a self,
+        user_id: &'a Thing,
+        keep: Option<&'a str>,
+    ) -> BoxFuture<'a, color_eyre::Result<()>> {
+        Box::pin(async move {
+            let mut conn = self.conn.clone();
+            let user_key = Self::user_key(user_id);
+            let session_ids: Vec<String> = conn.smembers(&user_key).await?;
+
+            for session_id in session_ids {
+                if Some(session_id.as_str()) == keep {
+                    continue;
+                }
+                let _: () = redis::pipe()
+                    .atomic()
+                    .del(Self::session_key(&session_id))
+                    .ignore()
+                    .srem(&user_key, &session_id)
+                    .ignore()
+                    .query_async(&mut conn)
+                    .await?;
+            }
+            Ok(())
+        })
+    }
+}
+// endregion: -- Redis Session Store
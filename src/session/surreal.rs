@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use color_eyre::eyre::Context;
+use futures_core::future::BoxFuture;
+use surrealdb::sql::Thing;
+
+use super::{SessionData, SessionStore};
+use crate::{db::Database, prometheus::time_query};
+
+// region: -- SurrealDB Session Store
+#[derive(Clone)]
+pub struct SurrealSessionStore {
+    database: Database,
+}
+
+impl SurrealSessionStore {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    fn record_id(session_id: &str) -> Thing {
+        Thing::from(("sessions".to_string(), session_id.to_string()))
+    }
+}
+
+impl SessionStore for SurrealSessionStore {
+    fn load<'a>(
+        &'a self,
+        session_id: &'a str,
+    ) -> BoxFuture<'a, color_eyre::Result<Option<SessionData>>> {
+        Box::pin(async move {
+            let sql = "SELECT data FROM $session WHERE expires_at > time::now()";
+
+            let mut res = time_query(
+                "session.load",
+                self.database
+                    .client
+                    .query(sql)
+                    .bind(("session", Self::record_id(session_id))),
+            )
+            .await
+            .context("Failed to load session")?
+            .check()?;
+
+            let data: Option<SessionData> = res.take((0, "data"))?;
+            Ok(data)
+        })
+    }
+
+    fn store<'a>(
+        &'a self,
+        session_id: &'a str,
+        data: &'a SessionData,
+        ttl: Duration,
+    ) -> BoxFuture<'a, color_eyre::Result<()>> {
+        Box::pin(async move {
+            // `ttl` is an integer, so formatting it into the query is safe
+            let sql = format!(
+                "DELETE sessions WHERE expires_at < time::now();
+                UPDATE $session SET data = $data, expires_at = time::now() + {}s;",
+                ttl.as_secs()
+            );
+
+            time_query(
+                "session.store",
+                self.database
+                    .client
+                    .query(sql)
+                    .bind(("session", Self::record_id(session_id)))
+                    .bind(("data", data)),
+            )
+            .await
+            .context("Failed to store session")?
+            .check()?;
+            Ok(())
+        })
+    }
+
+    fn delete<'a>(&'a self, session_id: &'a str) -> BoxFuture<'a, color_eyre::Result<()>> {
+        Box::pin(async move {
+            self.database
+                .client
+                .query("DELETE $session")
+                .bind(("session", Self::record_id(session_id)))
+                .await
+                .context("Failed to delete session")?
+                .check()?;
+            Ok(())
+        })
+    }
+
+    fn delete_for_user<'a>(
+        &'a self,
+        user_id: &'a Thing,
+        keep: Option<&'a str>,
+    ) -> BoxFuture<'a, color_eyre::Result<()>> {
+        Box::pin(async move {
+            let sql = "DELETE sessions WHERE data.user_id = $user_id AND id != $keep";
+
+            self.database
+                .client
+                .query(sql)
+                .bind(("user_id", user_id))
+                .bind(("keep", keep.map(Self::record_id)))
+                .await
+                .context("Failed to delete user sessions")?
+                .check()?;
+            Ok(())
+        })
+    }
+}
+// endregion: -- SurrealDB Session Store
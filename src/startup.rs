@@ -1,30 +1,49 @@
 use axum::extract::FromRef;
 use axum::{
-    routing::{get, post, IntoMakeService},
+    extract::connect_info::IntoMakeServiceWithConnectInfo,
+    middleware,
+    routing::{delete, get, post, put, IntoMakeService},
     Router, Server,
 };
-// use axum_session::{SessionLayer, SessionStore};
 use color_eyre::eyre::Context;
 use color_eyre::Result;
-use hyper::{server::conn::AddrIncoming, Body};
+use hyper::{server::conn::AddrIncoming, Body, Request};
 use secrecy::Secret;
-use std::{net::TcpListener, sync::Arc};
+use sentry::integrations::tower::{NewSentryLayer, SentryHttpLayer};
+use std::{
+    net::{SocketAddr, TcpListener},
+    sync::Arc,
+};
 use tower_cookies::CookieManagerLayer;
 use tower_http::trace::TraceLayer;
 use tracing::warn;
-use uuid::Uuid;
 
 use crate::{
-    configuration::Settings, db::Database, email_client::EmailClient, routes,
+    authentication::{dummy_password_hash, LoginThrottle},
+    configuration::Settings,
+    csrf::csrf_protection,
+    db::Database,
+    email_client::EmailClient,
+    openapi,
+    problem::render_problems,
+    prometheus::{self, handler_metrics, track_http_metrics},
+    request_id::propagate_request_id,
+    routes,
     routes::handler_confirm,
+    security_headers::security_headers,
+    session::{build_session_store, SessionStore},
+    telemetry,
 };
 
-type ZServer = Server<AddrIncoming, IntoMakeService<Router<(), Body>>>;
+type ZServer = Server<AddrIncoming, IntoMakeServiceWithConnectInfo<Router<(), Body>, SocketAddr>>;
+type MetricsServer = Server<AddrIncoming, IntoMakeService<Router<(), Body>>>;
 
 // region: -- Application
 pub struct Application {
     port: u16,
     server: ZServer,
+    metrics_port: Option<u16>,
+    metrics_server: Option<MetricsServer>,
 }
 
 impl Application {
@@ -38,18 +57,16 @@ impl Application {
         )
     )]
     pub async fn build(configuration: Settings, database: Database) -> Result<Self> {
-        let sender_email = configuration
-            .email_client
-            .sender()
-            .expect("Invalid sender email address.");
-
-        let timeout = configuration.email_client.timeout();
-        let email_client = EmailClient::new(
-            configuration.email_client.base_url.clone(),
-            sender_email,
-            configuration.email_client.authorization_token.clone(),
-            timeout,
-        );
+        prometheus::install_recorder();
+        let argon2 = configuration.authentication.argon2.clone();
+        telemetry::spawn_block_with_tracing(move || dummy_password_hash(&argon2))
+            .await
+            .context("Failed to spawn blocking task")?
+            .context("Failed to hash the dummy password")?;
+        let email_client = configuration.email_client.client();
+        let session_store = build_session_store(&configuration, &database)
+            .await
+            .context("Failed to build session store")?;
 
         let address = format!(
             "{}:{}",
@@ -57,11 +74,34 @@ impl Application {
         );
         let listener = TcpListener::bind(&address).context("Failed to bind to address")?;
         let port = listener.local_addr().unwrap().port();
-        let server = run(listener, configuration, email_client, database)
-            .await
-            .context("Server failed to run")?;
 
-        Ok(Self { port, server })
+        let (metrics_port, metrics_server) = match configuration.metrics.port {
+            Some(metrics_port) if configuration.metrics.enabled => {
+                let address = format!("{}:{}", configuration.application.host, metrics_port);
+                let listener =
+                    TcpListener::bind(&address).context("Failed to bind the metrics address")?;
+                let metrics_port = listener.local_addr().unwrap().port();
+                (Some(metrics_port), Some(run_metrics(listener)?))
+            }
+            _ => (None, None),
+        };
+
+        let server = run(
+            listener,
+            configuration,
+            email_client,
+            database,
+            session_store,
+        )
+        .await
+        .context("Server failed to run")?;
+
+        Ok(Self {
+            port,
+            server,
+            metrics_port,
+            metrics_server,
+        })
     }
     // endregion: -- Application Builder
 
@@ -69,16 +109,29 @@ impl Application {
         self.port
     }
 
+    /// The separate metrics port, when `metrics.port` is configured.
+    pub fn metrics_port(&self) -> Option<u16> {
+        self.metrics_port
+    }
+
     pub async fn run_until_stopped(self) -> Result<()> {
         let quit_sig = async {
             _ = tokio::signal::ctrl_c().await;
             warn!("Received Ctrl-C, shutting down gracefully...");
         };
 
-        self.server
+        // Scraping stops with the application
+        let metrics_server = self.metrics_server.map(tokio::spawn);
+
+        let result = self
+            .server
             .with_graceful_shutdown(quit_sig)
             .await
-            .map_err(|e| color_eyre::Report::msg(format!("Server failed to run: {e}")))
+            .map_err(|e| color_eyre::Report::msg(format!("Server failed to run: {e}")));
+        if let Some(metrics_server) = metrics_server {
+            metrics_server.abort();
+        }
+        result
     }
 }
 // endregion: -- Application
@@ -91,6 +144,8 @@ pub struct AppState {
     pub base_url: ApplicationBaseUrl,
     pub database: Database,
     pub secret: HmacSecret,
+    pub session_store: Arc<dyn SessionStore>,
+    pub login_throttle: LoginThrottle,
 }
 
 #[derive(Debug, Clone)]
@@ -120,6 +175,18 @@ impl FromRef<AppState> for Database {
     }
 }
 
+impl FromRef<AppState> for Arc<dyn SessionStore> {
+    fn from_ref(state: &AppState) -> Arc<dyn SessionStore> {
+        state.session_store.clone()
+    }
+}
+
+impl FromRef<AppState> for LoginThrottle {
+    fn from_ref(state: &AppState) -> LoginThrottle {
+        state.login_throttle.clone()
+    }
+}
+
 impl FromRef<AppState> for HmacSecret {
     fn from_ref(state: &AppState) -> HmacSecret {
         state.secret.clone()
@@ -136,44 +203,130 @@ pub async fn run(
     configuration: Settings,
     email_client: EmailClient,
     database: Database,
+    session_store: Arc<dyn SessionStore>,
 ) -> Result<ZServer> {
     let state = AppState {
         base_url: ApplicationBaseUrl(configuration.application.base_url.clone()),
         email_client: Arc::new(email_client),
         database,
         secret: HmacSecret(configuration.application.hmac_secret.clone()),
+        session_store,
+        login_throttle: LoginThrottle::new(configuration.authentication.throttle.clone()),
         configuration,
     };
+    let metrics = &state.configuration.metrics;
+    let serve_metrics_here = metrics.enabled && metrics.port.is_none();
 
-    // let redis_client = redis::Client::open(configuration.redis_uri.expose_secret()).unwrap();
-
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/", get(routes::home))
         .route("/login", get(routes::login_form))
         .route("/login", post(routes::login))
+        .route(
+            "/login/two-factor",
+            get(routes::login_two_factor_form).post(routes::login_two_factor),
+        )
+        .route("/logout", post(routes::logout))
         .route("/health_check", get(routes::handler_health_check))
+        .route("/health/live", get(routes::handler_liveness))
+        .route("/health/ready", get(routes::handler_readiness))
         .route("/subscribe", post(routes::handler_subscribe))
         .route("/subscribe/confirm", get(handler_confirm))
         .route("/newsletters", post(routes::publish_newsletter))
-        .layer(CookieManagerLayer::new())
-        // .layer(SessionLayer::new(todo!()))
-        .layer(
-            TraceLayer::new_for_http().make_span_with(|request: &hyper::Request<Body>| {
-                let uuid = Uuid::new_v4();
-                tracing::info_span!(
-                    "request",
-                    uuid = %uuid,
-                    method = %request.method(),
-                    uri = %request.uri(),
-                )
-            }),
+        .route("/subscribers", get(routes::list_subscribers))
+        .route("/admin/dashboard", get(routes::admin_dashboard))
+        .route("/admin/issues", get(routes::admin_issues))
+        .route(
+            "/admin/newsletters",
+            get(routes::admin_newsletter_form).post(routes::admin_publish_newsletter),
+        )
+        .route(
+            "/admin/password",
+            get(routes::admin_change_password_form).post(routes::admin_change_password),
+        )
+        .route(
+            "/admin/two-factor",
+            get(routes::admin_two_factor_form).post(routes::admin_enable_two_factor),
+        )
+        .route(
+            "/admin/tokens",
+            get(routes::admin_list_tokens).post(routes::admin_create_token),
+        )
+        .route(
+            "/admin/tokens/:token_id",
+            delete(routes::admin_revoke_token),
+        )
+        .route(
+            "/admin/users",
+            get(routes::admin_list_users).post(routes::admin_create_user),
+        )
+        .route(
+            "/admin/users/:username/password",
+            put(routes::admin_change_user_password),
+        )
+        .route(
+            "/admin/users/:username/role",
+            put(routes::admin_set_user_role),
+        )
+        .route(
+            "/admin/users/:username/deactivate",
+            post(routes::admin_deactivate_user),
         )
+        .route(
+            "/admin/subscribers/:email/status",
+            put(routes::admin_set_subscriber_status),
+        )
+        .route(
+            "/admin/log-level",
+            get(routes::admin_log_level).put(routes::admin_set_log_level),
+        )
+        .route("/admin/audit", get(routes::admin_audit_log));
+    if serve_metrics_here {
+        app = app.route("/metrics", get(handler_metrics));
+    }
+    app = app.route("/openapi.json", get(openapi::handler_openapi));
+    #[cfg(feature = "docs-ui")]
+    {
+        app = app
+            .route(
+                "/docs",
+                get(|| async { axum::response::Redirect::permanent("/docs/") }),
+            )
+            .route("/docs/", get(openapi::handler_docs))
+            .route("/docs/*tail", get(openapi::handler_docs));
+    }
+
+    let app = app
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            csrf_protection,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            security_headers,
+        ))
+        .layer(CookieManagerLayer::new())
+        .layer(middleware::from_fn(track_http_metrics))
+        .layer(TraceLayer::new_for_http().make_span_with(telemetry::make_request_span::<Body>))
+        // Reported errors carry the request's method, URL and headers
+        .layer(SentryHttpLayer::with_transaction())
+        .layer(NewSentryLayer::<Request<Body>>::new_from_top())
+        .layer(middleware::from_fn(render_problems))
+        .layer(middleware::from_fn(propagate_request_id))
         .with_state(state);
 
     let server = Server::from_tcp(listener)
         .unwrap_or_else(|e| {
             panic!("Failed to bind random port: {e}");
         })
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>());
+    Ok(server)
+}
+
+/// Serves only `/metrics`, so it can be kept off the public listener.
+pub fn run_metrics(listener: TcpListener) -> Result<MetricsServer> {
+    let app = Router::new().route("/metrics", get(handler_metrics));
+    let server = Server::from_tcp(listener)
+        .context("Failed to bind the metrics listener")?
         .serve(app.into_make_service());
     Ok(server)
 }
@@ -1,9 +1,38 @@
+use std::sync::{Arc, Mutex};
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Request};
+use futures_core::future::BoxFuture;
+use opentelemetry::{
+    global,
+    propagation::{Extractor, Injector},
+    sdk::{
+        export::trace::{ExportResult, SpanData, SpanExporter},
+        propagation::TraceContextPropagator,
+        trace::{self, Sampler, Tracer, TracerProvider},
+        Resource,
+    },
+    trace::{TraceError, TracerProvider as _},
+    KeyValue,
+};
+use opentelemetry_otlp::{SpanExporterBuilder, WithExportConfig};
+use secrecy::ExposeSecret;
+use sentry_tracing::EventFilter;
 use tokio::task::JoinHandle;
 use tracing::subscriber::set_global_default;
-use tracing::Subscriber;
+use tracing::{Level, Span, Subscriber};
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
-use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt, EnvFilter, Registry};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{
+    fmt::MakeWriter, layer::SubscriberExt, reload, EnvFilter, Layer, Registry,
+};
+
+use crate::{
+    configuration::{RedactionSettings, SentrySettings, TelemetrySettings},
+    log_filter::LogFilter,
+    redaction::{RedactionLayer, RedactionPolicy},
+    request_id::{RequestId, REQUEST_ID_HEADER},
+};
 
 // region: -- Spawn Blocking w/Tracing
 pub fn spawn_block_with_tracing<F, R>(f: F) -> JoinHandle<R>
@@ -17,49 +46,297 @@ where
 // region: -- Spawn Blocking w/Tracing
 
 // region: -- Tracing: Initialize
+/// `tracer` adds an OpenTelemetry layer exporting every span it samples; see
+/// [`init_tracer`] and [`test_tracer`]. The formatting, OpenTelemetry and
+/// Sentry layers sit behind a [`RedactionLayer`], so they only see fields the
+/// `redaction` policy lets through. The filter (`RUST_LOG`, falling back
+/// to `env_filter`) can be changed at runtime through [`LogFilter`].
 pub fn get_subscriber<Sink>(
     name: String,
     env_filter: String,
     sink: Sink,
+    tracer: Option<Tracer>,
+    redaction: &RedactionSettings,
 ) -> impl Subscriber + Send + Sync
 where
     Sink: for<'a> MakeWriter<'a> + Send + Sync + 'static,
 {
-    let env_filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(env_filter));
+    let directive = std::env::var(EnvFilter::DEFAULT_ENV)
+        .ok()
+        .filter(|directive| EnvFilter::try_new(directive).is_ok())
+        .unwrap_or(env_filter);
+    let (env_filter, handle) = reload::Layer::new(EnvFilter::new(&directive));
+    LogFilter::install(handle, directive);
     let formatting_layer = BunyanFormattingLayer::new(name, sink);
+    let otel_layer = tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer));
+    // Logged events only become breadcrumbs: errors are reported explicitly
+    // (see `report_unexpected_error`) so they carry their cause chain.
+    let sentry_layer = sentry_tracing::layer().event_filter(|metadata| match *metadata.level() {
+        Level::ERROR | Level::WARN | Level::INFO => EventFilter::Breadcrumb,
+        _ => EventFilter::Ignore,
+    });
+
+    // `Option` has an `and_then` of its own
+    let layers = Layer::and_then(otel_layer, sentry_layer)
+        .and_then(JsonStorageLayer)
+        .and_then(formatting_layer);
 
     Registry::default()
         .with(env_filter)
-        .with(JsonStorageLayer)
-        .with(formatting_layer)
+        .with(RedactionLayer::new(layers, RedactionPolicy::new(redaction)))
 }
 
 pub fn init_subscriber(subscriber: impl Subscriber + Send + Sync) {
     LogTracer::init().expect("Failed to set logger.");
+    global::set_text_map_propagator(TraceContextPropagator::new());
     set_global_default(subscriber).expect("Failed to set subscriber.");
 }
 // endregion: --- Tracing: Initialize
 
+// region: -- OpenTelemetry
+/// Batches spans to the configured OTLP (gRPC) collector. `None` when no
+/// endpoint is configured. Call `opentelemetry::global::shutdown_tracer_provider`
+/// before exiting to flush the last batch.
+pub fn init_tracer(
+    service_name: &str,
+    settings: &TelemetrySettings,
+) -> Result<Option<Tracer>, TraceError> {
+    let Some(endpoint) = &settings.otlp_endpoint else {
+        return Ok(None);
+    };
+
+    let exporter = SpanExporterBuilder::from(
+        opentelemetry_otlp::new_exporter()
+            .tonic()
+            .with_endpoint(endpoint),
+    )
+    .build_span_exporter()?;
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, opentelemetry::runtime::Tokio)
+        .with_config(trace_config(service_name, settings))
+        .build();
+    Ok(Some(install_provider(service_name, provider)))
+}
+
+/// Exports synchronously to `exporter`, so tests can assert on finished spans.
+pub fn test_tracer(
+    service_name: &str,
+    settings: &TelemetrySettings,
+    exporter: TestExporter,
+) -> Tracer {
+    let provider = TracerProvider::builder()
+        .with_simple_exporter(exporter)
+        .with_config(trace_config(service_name, settings))
+        .build();
+    install_provider(service_name, provider)
+}
+
+fn install_provider(service_name: &str, provider: TracerProvider) -> Tracer {
+    let tracer = provider.tracer(service_name.to_string());
+    // Tracers only hold a weak reference to their provider
+    global::set_tracer_provider(provider);
+    tracer
+}
+
+/// New traces are sampled at `sampling_ratio`; traces started upstream keep
+/// the caller's decision.
+fn trace_config(service_name: &str, settings: &TelemetrySettings) -> trace::Config {
+    trace::config()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            settings.sampling_ratio,
+        ))))
+        .with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            service_name.to_string(),
+        )]))
+}
+
+/// Collects exported spans in memory.
+#[derive(Clone, Debug, Default)]
+pub struct TestExporter {
+    spans: Arc<Mutex<Vec<SpanData>>>,
+}
+
+impl TestExporter {
+    pub fn spans(&self) -> Vec<SpanData> {
+        self.spans.lock().unwrap().clone()
+    }
+}
+
+impl SpanExporter for TestExporter {
+    fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+        self.spans.lock().unwrap().extend(batch);
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+// endregion: -- OpenTelemetry
+
+// region: -- Trace Context Propagation
+/// The root span of every HTTP request, continuing the caller's trace when
+/// it sent a W3C `traceparent`.
+pub fn make_request_span<B>(request: &Request<B>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        uri = %request.uri(),
+    );
+
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    span.set_parent(parent);
+    span
+}
+
+/// `traceparent`/`tracestate` for the current span, for outbound requests.
+pub fn trace_context_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let context = Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(&mut headers))
+    });
+    headers
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+// endregion: -- Trace Context Propagation
+
 // region: -- Sentry.io error reporting
-pub fn init_sentry() {
-    let _guard = sentry::init((
-        std::env::var("SENTRY_DSN").expect("$SENTRY_DSN must be set"),
+/// `None` when no DSN is configured. Events are only sent while the returned
+/// guard is alive, so hold it until the process exits. Whatever reaches
+/// Sentry without passing the `RedactionLayer`, such as the errors from
+/// [`report_unexpected_error`], is redacted here.
+pub fn init_sentry(
+    settings: &SentrySettings,
+    redaction: &RedactionSettings,
+) -> Option<sentry::ClientInitGuard> {
+    let dsn = settings.dsn.as_ref()?;
+    let environment = settings
+        .environment
+        .clone()
+        .or_else(|| std::env::var("APP_ENVIRONMENT").ok())
+        .unwrap_or_else(|| "local".into());
+    let policy = Arc::new(RedactionPolicy::new(redaction));
+
+    Some(sentry::init((
+        dsn.expose_secret().as_str(),
         sentry::ClientOptions {
             release: sentry::release_name!(),
+            environment: Some(environment.into()),
+            traces_sample_rate: settings.traces_sample_rate,
+            before_send: Some(Arc::new({
+                let policy = policy.clone();
+                move |event| Some(redact_sentry_event(&policy, event))
+            })),
+            before_breadcrumb: Some(Arc::new(move |breadcrumb| {
+                Some(redact_breadcrumb(&policy, breadcrumb))
+            })),
             ..Default::default()
         },
-    ));
+    )))
+}
+
+fn redact_sentry_event(
+    policy: &RedactionPolicy,
+    mut event: sentry::protocol::Event<'static>,
+) -> sentry::protocol::Event<'static> {
+    event.message = event.message.map(|message| policy.scrub(&message));
+    event.extra = event
+        .extra
+        .into_iter()
+        .filter_map(|(field, value)| policy.redact(&field, value).map(|value| (field, value)))
+        .collect();
+    for exception in event.exception.values.iter_mut() {
+        exception.value = exception.value.as_deref().map(|value| policy.scrub(value));
+    }
+    event.breadcrumbs.values = std::mem::take(&mut event.breadcrumbs.values)
+        .into_iter()
+        .map(|breadcrumb| redact_breadcrumb(policy, breadcrumb))
+        .collect();
+    event
+}
+
+fn redact_breadcrumb(
+    policy: &RedactionPolicy,
+    mut breadcrumb: sentry::Breadcrumb,
+) -> sentry::Breadcrumb {
+    breadcrumb.message = breadcrumb.message.map(|message| policy.scrub(&message));
+    breadcrumb.data = breadcrumb
+        .data
+        .into_iter()
+        .filter_map(|(field, value)| policy.redact(&field, value).map(|value| (field, value)))
+        .collect();
+    breadcrumb
+}
+
+/// Reports an unexpected error with its `error_chain_fmt` cause chain,
+/// tagged with the request id. Request details come from the per-request
+/// hub set up in `startup::run`.
+pub fn report_unexpected_error<E>(error: &E)
+where
+    E: std::error::Error + ?Sized,
+{
+    let mut event = sentry::event_from_error(error);
+    event
+        .extra
+        .insert("cause_chain".into(), format!("{:?}", error).into());
+    if let Some(request_id) = RequestId::current() {
+        event
+            .tags
+            .insert("request_id".into(), request_id.as_str().into());
+    }
+    sentry::capture_event(event);
 }
 // endregion: --- Sentry.io error reporting
 
-// region: -- Honeycomb.io tracing
-pub fn init_honeycomb() {
-    let (_honeyguard, _tracer) = opentelemetry_honeycomb::new_pipeline(
-        std::env::var("HONEYCOMB_API_KEY").expect("HONEYCOMB_API_KEY must be set"),
-        "zero2axum".into(),
-    )
-    .install()
-    .unwrap();
+#[cfg(test)]
+mod tests {
+    use color_eyre::eyre::eyre;
+
+    use super::report_unexpected_error;
+    use crate::error::SubscribeError;
+
+    #[test]
+    fn unexpected_errors_are_reported_with_their_cause_chain() {
+        let error = SubscribeError::UnexpectedError(
+            eyre!("connection refused").wrap_err("Failed to insert new subscriber."),
+        );
+
+        let events = sentry::test::with_captured_events(|| report_unexpected_error(&error));
+
+        assert_eq!(events.len(), 1);
+        let cause_chain = events[0].extra["cause_chain"].as_str().unwrap();
+        assert!(cause_chain.contains("Failed to insert new subscriber."));
+        assert!(cause_chain.contains("Caused by:\n\tconnection refused"));
+        assert!(!events[0].exception.values.is_empty());
+    }
 }
-// endregion: --- Honeycomb.io tracing
@@ -0,0 +1,91 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app};
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+// region: -- Dashboard requires a session
+#[tokio::test]
+async fn anonymous_users_cannot_see_the_dashboard() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_admin_dashboard().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+// endregion: -- Dashboard requires a session
+
+// region: -- Dashboard shows subscriber statistics
+#[tokio::test]
+async fn dashboard_lists_recent_signups() {
+    // Arrange
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+    app.post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com".into())
+        .await
+        .error_for_status()
+        .unwrap();
+    app.login_as_test_user().await;
+
+    // Act
+    let html = app.get_admin_dashboard_html().await;
+
+    // Assert
+    assert!(html.contains("ursula_le_guin@gmail.com"));
+    assert!(html.contains("pending_confirmation"));
+}
+// endregion: -- Dashboard shows subscriber statistics
+
+// region: -- Publishing through the form
+#[tokio::test]
+async fn publishing_through_the_form_records_the_issue() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app
+        .post_admin_newsletters(&serde_json::json!({
+            "title": "Newsletter title",
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/issues");
+    let html = app.get_admin_issues_html().await;
+    assert!(html.contains("The newsletter issue has been published"));
+    assert!(html.contains("Newsletter title"));
+    assert!(html.contains(&app.test_user.username));
+}
+
+#[tokio::test]
+async fn an_incomplete_form_is_not_published() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_admin_newsletters(&serde_json::json!({
+            "title": "",
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+}
+// endregion: -- Publishing through the form
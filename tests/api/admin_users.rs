@@ -0,0 +1,162 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app};
+use uuid::Uuid;
+
+// region: -- Admin API requires a session
+#[tokio::test]
+async fn anonymous_users_are_redirected_to_the_login_form() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_admin_users(&serde_json::json!({
+            "username": "ursula",
+            "password": "le-guin"
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+// endregion: -- Admin API requires a session
+
+// region: -- Created users can log in
+#[tokio::test]
+async fn a_user_created_through_the_admin_api_can_log_in() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let username = Uuid::new_v4().to_string();
+    let password = Uuid::new_v4().to_string();
+
+    // Act 1 - Create the user
+    let response = app
+        .post_admin_users(&serde_json::json!({
+            "username": username,
+            "password": password
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    // Act 2 - Log in as the new user
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": username,
+            "password": password
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+// endregion: -- Created users can log in
+
+// region: -- Duplicate usernames are rejected
+#[tokio::test]
+async fn creating_a_user_with_an_existing_username_returns_409() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app
+        .post_admin_users(&serde_json::json!({
+            "username": app.test_user.username,
+            "password": Uuid::new_v4().to_string()
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 409);
+}
+// endregion: -- Duplicate usernames are rejected
+
+// region: -- Password changes take effect
+#[tokio::test]
+async fn changing_a_users_password_replaces_the_old_one() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let username = Uuid::new_v4().to_string();
+    let old_password = Uuid::new_v4().to_string();
+    let new_password = Uuid::new_v4().to_string();
+    app.post_admin_users(&serde_json::json!({
+        "username": username,
+        "password": old_password
+    }))
+    .await
+    .error_for_status()
+    .unwrap();
+
+    // Act
+    let response = app
+        .put_admin_user_password(&username, &serde_json::json!({ "password": new_password }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Assert
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": username,
+            "password": old_password
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/login");
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": username,
+            "password": new_password
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+// endregion: -- Password changes take effect
+
+// region: -- Deactivated users cannot log in
+#[tokio::test]
+async fn deactivated_users_cannot_log_in() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let username = Uuid::new_v4().to_string();
+    let password = Uuid::new_v4().to_string();
+    app.post_admin_users(&serde_json::json!({
+        "username": username,
+        "password": password
+    }))
+    .await
+    .error_for_status()
+    .unwrap();
+
+    // Act
+    let response = app.post_admin_deactivate_user(&username).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Assert
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": username,
+            "password": password
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/login");
+}
+// endregion: -- Deactivated users cannot log in
+
+// region: -- Unknown users return 404
+#[tokio::test]
+async fn deactivating_an_unknown_user_returns_404() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app
+        .post_admin_deactivate_user(&Uuid::new_v4().to_string())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+// endregion: -- Unknown users return 404
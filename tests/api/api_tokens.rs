@@ -0,0 +1,132 @@
+use crate::helpers::{spawn_app, TestApp};
+
+fn newsletter_body() -> serde_json::Value {
+    serde_json::json!({
+        "title": "Newsletter title",
+        "content": {
+            "text": "Newsletter body as plain text",
+            "html": "<p>Newsletter body as HTML</p>",
+        }
+    })
+}
+
+async fn create_token(app: &TestApp, scopes: &[&str]) -> (String, String) {
+    let response = app
+        .post_admin_tokens(&serde_json::json!({ "name": "ci", "scopes": scopes }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    let body: serde_json::Value = response.json().await.unwrap();
+    (
+        body["id"].as_str().unwrap().to_string(),
+        body["token"].as_str().unwrap().to_string(),
+    )
+}
+
+// region: -- Token management
+#[tokio::test]
+async fn tokens_are_only_shown_on_creation() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let (id, token) = create_token(&app, &["publish"]).await;
+
+    // Act
+    let tokens: serde_json::Value = app.get_admin_tokens().await.json().await.unwrap();
+
+    // Assert
+    let tokens = tokens.as_array().unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0]["id"], id.as_str());
+    assert_eq!(tokens[0]["name"], "ci");
+    assert!(tokens[0].get("token").is_none());
+    assert!(!tokens[0].to_string().contains(&token));
+}
+
+#[tokio::test]
+async fn a_token_without_scopes_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app
+        .post_admin_tokens(&serde_json::json!({ "name": "ci", "scopes": [] }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+// endregion: -- Token management
+
+// region: -- Bearer authentication
+#[tokio::test]
+async fn a_publish_token_can_publish_and_records_its_use() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let (_, token) = create_token(&app, &["publish"]).await;
+
+    // Act
+    let response = app
+        .post_newsletters_with_token(&token, newsletter_body())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let tokens: serde_json::Value = app.get_admin_tokens().await.json().await.unwrap();
+    assert!(tokens[0]["last_used_at"].is_string());
+}
+
+#[tokio::test]
+async fn a_token_without_the_publish_scope_cannot_publish() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let (_, token) = create_token(&app, &["subscribers:read"]).await;
+
+    // Act
+    let response = app
+        .post_newsletters_with_token(&token, newsletter_body())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn a_revoked_token_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let (id, token) = create_token(&app, &["publish"]).await;
+
+    // Act
+    let response = app.delete_admin_token(&id).await;
+    assert_eq!(response.status().as_u16(), 204);
+
+    // Assert
+    let response = app
+        .post_newsletters_with_token(&token, newsletter_body())
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn subscribers_can_be_listed_with_a_read_token() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let (_, read_token) = create_token(&app, &["subscribers:read"]).await;
+    let (_, publish_token) = create_token(&app, &["publish"]).await;
+
+    // Act
+    let allowed = app.get_subscribers(&read_token).await;
+    let denied = app.get_subscribers(&publish_token).await;
+
+    // Assert
+    assert_eq!(allowed.status().as_u16(), 200);
+    let subscribers: serde_json::Value = allowed.json().await.unwrap();
+    assert!(subscribers.is_array());
+    assert_eq!(denied.status().as_u16(), 401);
+}
+// endregion: -- Bearer authentication
@@ -0,0 +1,254 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app, spawn_app_with, TestApp, TestUser};
+
+async fn audit_entries(app: &TestApp, query: &[(&str, &str)]) -> Vec<serde_json::Value> {
+    let response = app.get_admin_audit(query).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    body["entries"].as_array().unwrap().clone()
+}
+
+async fn store_subscriber(app: &TestApp, email: &str, status: &str) {
+    app.database
+        .client
+        .query(
+            "CREATE subscriptions CONTENT { email: $email, name: 'le guin', \
+            subscribed_at: time::now(), status: $status }",
+        )
+        .bind(("email", email))
+        .bind(("status", status))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn failed_logins_are_recorded() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_login(&serde_json::json!({
+        "username": &app.test_user.username,
+        "password": "wrong-password"
+    }))
+    .await;
+
+    // Act
+    app.login_as_test_user().await;
+    let entries = audit_entries(&app, &[("action", "login.*")]).await;
+
+    // Assert
+    let actions: Vec<&str> = entries
+        .iter()
+        .map(|e| e["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(actions, vec!["login.success", "login.failure"]);
+    let failure = &entries[1];
+    assert_eq!(failure["actor"], app.test_user.username.as_str());
+    assert_eq!(failure["ip"], "127.0.0.1");
+    assert!(failure["user_agent"].is_string());
+}
+
+#[tokio::test]
+async fn the_client_ip_comes_from_the_trusted_header() {
+    // Arrange
+    let app = spawn_app_with(|c| {
+        c.authentication.throttle.client_ip_header = Some("X-Forwarded-For".into())
+    })
+    .await;
+    app.api_client
+        .post(&format!(
+            "http://{}:{}/login",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .form(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": "wrong-password"
+        }))
+        .header("X-CSRF-Token", app.csrf_token().await)
+        .header("X-Forwarded-For", "10.0.0.1, 203.0.113.7")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Act
+    app.login_as_test_user().await;
+    let entries = audit_entries(&app, &[("action", "login.failure")]).await;
+
+    // Assert
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["ip"], "203.0.113.7");
+}
+
+#[tokio::test]
+async fn lockouts_are_recorded_once() {
+    // Arrange
+    let app = spawn_app().await;
+    let max_failures = app
+        .configuration
+        .authentication
+        .throttle
+        .max_failures_per_username;
+    let login_body = serde_json::json!({
+        "username": "random-username",
+        "password": "random-password"
+    });
+    // Attempts made during the lockout neither extend nor re-record it
+    for _ in 0..max_failures + 2 {
+        app.post_login(&login_body).await;
+    }
+
+    // Act
+    app.login_as_test_user().await;
+    let entries = audit_entries(&app, &[("action", "login.lockout")]).await;
+
+    // Assert
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["actor"], "random-username");
+    assert_eq!(entries[0]["diff"]["failures"], max_failures);
+}
+
+#[tokio::test]
+async fn publishing_records_who_sent_the_issue() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    app.post_admin_newsletters(&serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+    }))
+    .await;
+
+    // Assert
+    let entries = audit_entries(&app, &[("action", "newsletter.publish")]).await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["actor"], app.test_user.username.as_str());
+    assert_eq!(entries[0]["target"], "Newsletter title");
+    assert_eq!(entries[0]["diff"]["via"], "form");
+}
+
+#[tokio::test]
+async fn role_changes_are_recorded_with_a_diff() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let user = TestUser::with_role("viewer");
+    user.store(&app.database.client).await;
+
+    // Act
+    app.put_admin_user_role(&user.username, "editor").await;
+
+    // Assert
+    let entries = audit_entries(&app, &[("target", &user.username)]).await;
+    assert_eq!(entries[0]["action"], "user.role_change");
+    assert_eq!(
+        entries[0]["diff"],
+        serde_json::json!({ "role": { "from": "viewer", "to": "editor" } })
+    );
+}
+
+#[tokio::test]
+async fn subscriber_status_overrides_are_recorded() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    store_subscriber(&app, "ursula_le_guin@gmail.com", "pending_confirmation").await;
+
+    // Act
+    let response = app
+        .put_admin_subscriber_status("ursula_le_guin@gmail.com", "confirmed")
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let entries = audit_entries(&app, &[("action", "subscriber.status_override")]).await;
+    assert_eq!(entries[0]["target"], "ursula_le_guin@gmail.com");
+    assert_eq!(
+        entries[0]["diff"],
+        serde_json::json!({ "status": { "from": "pending_confirmation", "to": "confirmed" } })
+    );
+}
+
+#[tokio::test]
+async fn unknown_subscriber_statuses_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    store_subscriber(&app, "ursula_le_guin@gmail.com", "pending_confirmation").await;
+
+    // Act
+    let invalid = app
+        .put_admin_subscriber_status("ursula_le_guin@gmail.com", "banana")
+        .await;
+    let unknown = app
+        .put_admin_subscriber_status("nobody@example.com", "confirmed")
+        .await;
+
+    // Assert
+    assert_eq!(invalid.status().as_u16(), 400);
+    assert_eq!(unknown.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn the_audit_log_is_paged_newest_first() {
+    // Arrange
+    let app = spawn_app().await;
+    for _ in 0..3 {
+        app.post_login(&serde_json::json!({
+            "username": "random-username",
+            "password": "random-password"
+        }))
+        .await;
+    }
+    app.login_as_test_user().await;
+
+    // Act
+    let first = audit_entries(&app, &[("per_page", "2"), ("page", "1")]).await;
+    let second = audit_entries(&app, &[("per_page", "2"), ("page", "2")]).await;
+
+    // Assert
+    assert_eq!(first.len(), 2);
+    assert_eq!(second.len(), 2);
+    assert_eq!(first[0]["action"], "login.success");
+    assert!(second
+        .iter()
+        .all(|e| e["actor"] == "random-username" && e["action"] == "login.failure"));
+}
+
+#[tokio::test]
+async fn malformed_timestamps_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app.get_admin_audit(&[("since", "last tuesday")]).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    let problem: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(problem["code"], "bad_request");
+}
+
+#[tokio::test]
+async fn only_owners_can_read_the_audit_log() {
+    // Arrange
+    let app = spawn_app().await;
+    let editor = TestUser::with_role("editor");
+    editor.store(&app.database.client).await;
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &editor.username,
+            "password": &editor.password
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+
+    // Act
+    let response = app.get_admin_audit(&[]).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+}
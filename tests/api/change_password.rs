@@ -0,0 +1,189 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app};
+use uuid::Uuid;
+
+// region: -- Requires a session
+#[tokio::test]
+async fn you_must_be_logged_in_to_change_your_password() {
+    // Arrange
+    let app = spawn_app().await;
+    let new_password = Uuid::new_v4().to_string();
+
+    // Act
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "current_password": Uuid::new_v4().to_string(),
+            "new_password": &new_password,
+            "new_password_check": &new_password,
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+// endregion: -- Requires a session
+
+// region: -- Form validation
+#[tokio::test]
+async fn new_password_fields_must_match() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "current_password": &app.test_user.password,
+            "new_password": Uuid::new_v4().to_string(),
+            "new_password_check": Uuid::new_v4().to_string(),
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/password");
+    let html = app.get_change_password_html().await;
+    assert!(html.contains("You entered two different new passwords"));
+}
+
+#[tokio::test]
+async fn current_password_must_be_valid() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let new_password = Uuid::new_v4().to_string();
+
+    // Act
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "current_password": Uuid::new_v4().to_string(),
+            "new_password": &new_password,
+            "new_password_check": &new_password,
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/password");
+    let html = app.get_change_password_html().await;
+    assert!(html.contains("The current password is incorrect."));
+}
+
+#[tokio::test]
+async fn new_password_must_satisfy_the_policy() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "current_password": &app.test_user.password,
+            "new_password": "short",
+            "new_password_check": "short",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/password");
+    let html = app.get_change_password_html().await;
+    assert!(html.contains("The password must be at least"));
+}
+
+#[tokio::test]
+async fn queued_errors_are_all_shown_once() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let mismatched = serde_json::json!({
+        "current_password": &app.test_user.password,
+        "new_password": Uuid::new_v4().to_string(),
+        "new_password_check": Uuid::new_v4().to_string(),
+    });
+    let new_password = Uuid::new_v4().to_string();
+    let wrong_current = serde_json::json!({
+        "current_password": Uuid::new_v4().to_string(),
+        "new_password": &new_password,
+        "new_password_check": &new_password,
+    });
+
+    // Act 1 - Fail twice without reading the page in between
+    app.post_change_password(&mismatched).await;
+    app.post_change_password(&wrong_current).await;
+
+    // Assert
+    let html = app.get_change_password_html().await;
+    let first = html
+        .find(r#"<p class="flash error"><i>You entered two different new passwords"#)
+        .expect("The first message is missing.");
+    let second = html
+        .find(r#"<p class="flash error"><i>The current password is incorrect.</i></p>"#)
+        .expect("The second message is missing.");
+    assert!(first < second);
+
+    // Act 2 - Reload the page
+    let html = app.get_change_password_html().await;
+    assert!(!html.contains(r#"<p class="flash"#));
+}
+// endregion: -- Form validation
+
+// region: -- Changing the password
+#[tokio::test]
+async fn changing_password_works_and_ends_other_sessions() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_url = format!(
+        "http://{}:{}/login",
+        &app.configuration.application.host, &app.configuration.application.port
+    );
+    let other_client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .cookie_store(true)
+        .build()
+        .unwrap();
+    let csrf_token = app.csrf_token_for(&other_client).await;
+    let response = other_client
+        .post(&login_url)
+        .header("X-CSRF-Token", csrf_token)
+        .form(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_is_redirect_to(&response, "/admin/dashboard");
+    app.login_as_test_user().await;
+    let new_password = Uuid::new_v4().to_string();
+
+    // Act
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "current_password": &app.test_user.password,
+            "new_password": &new_password,
+            "new_password_check": &new_password,
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/password");
+    let html = app.get_change_password_html().await;
+    assert!(html.contains("Your password has been changed."));
+
+    let response = other_client
+        .get(&format!(
+            "http://{}:{}/admin/dashboard",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_is_redirect_to(&response, "/login");
+
+    app.post_logout().await;
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &new_password
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+// endregion: -- Changing the password
@@ -0,0 +1,189 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+
+fn login_url(app: &TestApp) -> String {
+    format!(
+        "http://{}:{}/login",
+        &app.configuration.application.host, &app.configuration.application.port
+    )
+}
+
+#[tokio::test]
+async fn form_posts_without_a_csrf_token_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.csrf_token().await;
+
+    // Act 1 - Login
+    let response = app
+        .api_client
+        .post(&login_url(&app))
+        .form(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+
+    // Act 2 - Subscribe
+    let response = app
+        .api_client
+        .post(&format!(
+            "http://{}:{}/subscribe",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .form(&serde_json::json!({
+            "name": "le guin",
+            "email": "ursula_le_guin@gmail.com"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn a_token_from_another_client_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let attacker = reqwest::Client::builder()
+        .cookie_store(true)
+        .build()
+        .unwrap();
+    let attacker_token = app.csrf_token_for(&attacker).await;
+    app.csrf_token().await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(&login_url(&app))
+        .form(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password,
+            "csrf_token": attacker_token,
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn the_rendered_form_token_is_accepted() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_html = app.get_login_html().await;
+    let csrf_token = app.csrf_token().await;
+    assert!(login_html.contains(&format!(
+        r#"<input type="hidden" name="csrf_token" value="{}">"#,
+        csrf_token
+    )));
+
+    // Act
+    let response = app
+        .api_client
+        .post(&login_url(&app))
+        .form(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password,
+            "csrf_token": csrf_token,
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+
+#[tokio::test]
+async fn json_api_calls_do_not_need_a_csrf_token() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_newsletters(serde_json::json!({
+            "title": "Newsletter title",
+            "content": {
+                "text": "Newsletter body as plain text",
+                "html": "<p>Newsletter body as HTML</p>",
+            }
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn json_sent_with_a_session_cookie_needs_a_csrf_token() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(&format!(
+            "http://{}:{}/admin/tokens",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .json(&serde_json::json!({ "name": "ci", "scopes": ["publish"] }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn basic_credentials_do_not_exempt_a_form_post() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act - What a browser with cached credentials sends for a cross-site form
+    let response = app
+        .api_client
+        .post(&format!(
+            "http://{}:{}/admin/newsletters",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .basic_auth(&app.test_user.username, Some(&app.test_user.password))
+        .form(&serde_json::json!({
+            "title": "Newsletter title",
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn oversized_form_bodies_are_not_buffered() {
+    // Arrange
+    let app = spawn_app().await;
+    app.csrf_token().await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(&login_url(&app))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(format!("username={}", "a".repeat(512 * 1024)))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 413);
+}
@@ -1,4 +1,15 @@
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, spawn_app_with, TestApp};
+
+async fn get_health(app: &TestApp, probe: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(&format!(
+            "http://{}:{}/health/{}",
+            &app.configuration.application.host, &app.configuration.application.port, probe
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
 
 // region: -- GET: 200 OK
 #[tokio::test]
@@ -22,3 +33,82 @@ async fn health_check_works() {
     assert_eq!(Some(0), response.content_length());
 }
 // endregion: -- GET: 200 OK
+
+// region: -- GET /health/live
+#[tokio::test]
+async fn liveness_reports_the_build() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = get_health(&app, "live").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    assert!(body["git_sha"].is_string());
+}
+// endregion: -- GET /health/live
+
+// region: -- GET /health/ready
+#[tokio::test]
+async fn readiness_probes_the_database() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = get_health(&app, "ready").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["checks"]["database"]["status"], "ok");
+    assert!(body["checks"]["database"]["latency_ms"].is_u64());
+    assert!(body["checks"].get("email").is_none());
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+}
+
+#[tokio::test]
+async fn readiness_fails_when_a_probe_times_out() {
+    // Arrange
+    // No SurrealDB round trip completes within 0ms
+    let app = spawn_app_with(|c| c.health.timeout_milliseconds = 0).await;
+
+    // Act
+    let response = get_health(&app, "ready").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 503);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "unavailable");
+    assert_eq!(body["checks"]["database"]["status"], "unavailable");
+}
+
+#[tokio::test]
+async fn readiness_optionally_probes_the_email_provider() {
+    // Arrange
+    let reachable = spawn_app_with(|c| c.health.probe_email = true).await;
+    let unreachable = spawn_app_with(|c| {
+        c.health.probe_email = true;
+        c.email_client.base_url = "http://127.0.0.1:1".into();
+    })
+    .await;
+
+    // Act
+    let ok = get_health(&reachable, "ready").await;
+    let failed = get_health(&unreachable, "ready").await;
+
+    // Assert
+    assert_eq!(ok.status().as_u16(), 200);
+    let body: serde_json::Value = ok.json().await.unwrap();
+    assert_eq!(body["checks"]["email"]["status"], "ok");
+
+    assert_eq!(failed.status().as_u16(), 503);
+    let body: serde_json::Value = failed.json().await.unwrap();
+    assert_eq!(body["checks"]["database"]["status"], "ok");
+    assert_eq!(body["checks"]["email"]["status"], "unavailable");
+}
+// endregion: -- GET /health/ready
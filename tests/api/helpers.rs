@@ -4,21 +4,42 @@ use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
 use uuid::Uuid;
 use wiremock::MockServer;
 use zero2axum::{
-    configuration::{get_configuration, Settings},
+    configuration::{get_configuration, RedactionSettings, Settings, TelemetrySettings},
     db::Database,
     startup::Application,
-    telemetry::{get_subscriber, init_subscriber},
+    telemetry::{get_subscriber, init_subscriber, test_tracer, TestExporter},
 };
 
 // region: -- conditional tracing for tests
+/// Every span finished by any test app, shared across the test binary.
+pub static TEST_EXPORTER: Lazy<TestExporter> = Lazy::new(TestExporter::default);
+
 static TRACING: Lazy<()> = Lazy::new(|| {
     let default_filter_level = "info".to_string();
     let subscriber_name = "test".to_string();
+    let redaction = RedactionSettings::default();
+    let tracer = test_tracer(
+        &subscriber_name,
+        &TelemetrySettings::default(),
+        TEST_EXPORTER.clone(),
+    );
     if std::env::var("TEST_LOG").is_ok() {
-        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::stdout);
+        let subscriber = get_subscriber(
+            subscriber_name,
+            default_filter_level,
+            std::io::stdout,
+            Some(tracer),
+            &redaction,
+        );
         init_subscriber(subscriber);
     } else {
-        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::sink);
+        let subscriber = get_subscriber(
+            subscriber_name,
+            default_filter_level,
+            std::io::sink,
+            Some(tracer),
+            &redaction,
+        );
         init_subscriber(subscriber);
     }
     color_eyre::install().expect("Failed to install `color_eyre`");
@@ -41,6 +62,29 @@ pub struct TestApp {
 }
 
 impl TestApp {
+    /// The token the home page renders into its forms for this client.
+    pub async fn csrf_token(&self) -> String {
+        self.csrf_token_for(&self.api_client).await
+    }
+
+    pub async fn csrf_token_for(&self, client: &reqwest::Client) -> String {
+        let html = client
+            .get(&format!(
+                "http://{}:{}/",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap();
+        let (_, rest) = html
+            .split_once(r#"name="csrf_token" value=""#)
+            .expect("No CSRF token was rendered.");
+        rest.split('"').next().unwrap().to_string()
+    }
+
     pub async fn get_login_html(&self) -> String {
         self.api_client
             .get(&format!(
@@ -65,11 +109,34 @@ impl TestApp {
                 &self.configuration.application.host, &self.configuration.application.port
             ))
             .form(body)
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_logout(&self) -> reqwest::Response {
+        self.api_client
+            .post(&format!(
+                "http://{}:{}/logout",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
+    pub async fn login_as_test_user(&self) {
+        let response = self
+            .post_login(&serde_json::json!({
+                "username": &self.test_user.username,
+                "password": &self.test_user.password
+            }))
+            .await;
+        assert_is_redirect_to(&response, "/admin/dashboard");
+    }
+
     pub async fn post_subscriptions(&self, body: String) -> reqwest::Response {
         self.api_client
             .post(&format!(
@@ -78,6 +145,35 @@ impl TestApp {
             ))
             .header("Content-Type", "application/x-www-form-urlencoded")
             .body(body.to_string())
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_subscriptions_json(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(&format!(
+                "http://{}:{}/subscribe",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Submits the subscribe form the way a browser does.
+    pub async fn post_subscriptions_html(&self, body: String) -> reqwest::Response {
+        self.api_client
+            .post(&format!(
+                "http://{}:{}/subscribe",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Accept", "text/html,application/xhtml+xml,*/*;q=0.8")
+            .body(body)
+            .header("X-CSRF-Token", self.csrf_token().await)
             .send()
             .await
             .expect("Failed to execute request.")
@@ -114,11 +210,357 @@ impl TestApp {
                 &self.configuration.application.host, &self.configuration.application.port
             ))
             .basic_auth(&self.test_user.username, Some(&self.test_user.password))
+            .header("X-CSRF-Token", self.csrf_token().await)
             .json(&body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
+
+    pub async fn post_admin_users(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(&format!(
+                "http://{}:{}/admin/users",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_users(&self) -> reqwest::Response {
+        self.api_client
+            .get(&format!(
+                "http://{}:{}/admin/users",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_user_password(
+        &self,
+        username: &str,
+        body: &serde_json::Value,
+    ) -> reqwest::Response {
+        self.api_client
+            .put(&format!(
+                "http://{}:{}/admin/users/{}/password",
+                &self.configuration.application.host,
+                &self.configuration.application.port,
+                username
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_admin_deactivate_user(&self, username: &str) -> reqwest::Response {
+        self.api_client
+            .post(&format!(
+                "http://{}:{}/admin/users/{}/deactivate",
+                &self.configuration.application.host,
+                &self.configuration.application.port,
+                username
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_dashboard(&self) -> reqwest::Response {
+        self.api_client
+            .get(&format!(
+                "http://{}:{}/admin/dashboard",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_dashboard_html(&self) -> String {
+        self.get_admin_dashboard().await.text().await.unwrap()
+    }
+
+    pub async fn get_admin_issues_html(&self) -> String {
+        self.api_client
+            .get(&format!(
+                "http://{}:{}/admin/issues",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_admin_newsletters<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(&format!(
+                "http://{}:{}/admin/newsletters",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .form(body)
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_change_password_html(&self) -> String {
+        self.api_client
+            .get(&format!(
+                "http://{}:{}/admin/password",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_change_password<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(&format!(
+                "http://{}:{}/admin/password",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .form(body)
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_two_factor_html(&self) -> String {
+        self.api_client
+            .get(&format!(
+                "http://{}:{}/admin/two-factor",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_two_factor(&self, code: &str) -> reqwest::Response {
+        self.api_client
+            .post(&format!(
+                "http://{}:{}/admin/two-factor",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .form(&serde_json::json!({ "code": code }))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_login_two_factor(&self, code: &str) -> reqwest::Response {
+        self.api_client
+            .post(&format!(
+                "http://{}:{}/login/two-factor",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .form(&serde_json::json!({ "code": code }))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_admin_tokens(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(&format!(
+                "http://{}:{}/admin/tokens",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_tokens(&self) -> reqwest::Response {
+        self.api_client
+            .get(&format!(
+                "http://{}:{}/admin/tokens",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_admin_token(&self, token_id: &str) -> reqwest::Response {
+        self.api_client
+            .delete(&format!(
+                "http://{}:{}/admin/tokens/{}",
+                &self.configuration.application.host,
+                &self.configuration.application.port,
+                token_id
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_newsletters_with_token(
+        &self,
+        token: &str,
+        body: serde_json::Value,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(&format!(
+                "http://{}:{}/newsletters",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_subscribers(&self, token: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(&format!(
+                "http://{}:{}/subscribers",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_user_role(&self, username: &str, role: &str) -> reqwest::Response {
+        self.api_client
+            .put(&format!(
+                "http://{}:{}/admin/users/{}/role",
+                &self.configuration.application.host,
+                &self.configuration.application.port,
+                username
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .json(&serde_json::json!({ "role": role }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_audit(&self, query: &[(&str, &str)]) -> reqwest::Response {
+        self.api_client
+            .get(&format!(
+                "http://{}:{}/admin/audit",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_subscriber_status(
+        &self,
+        email: &str,
+        status: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .put(&format!(
+                "http://{}:{}/admin/subscribers/{}/status",
+                &self.configuration.application.host, &self.configuration.application.port, email
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .json(&serde_json::json!({ "status": status }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_log_level(&self) -> reqwest::Response {
+        self.api_client
+            .get(&format!(
+                "http://{}:{}/admin/log-level",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_log_level(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .put(&format!(
+                "http://{}:{}/admin/log-level",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_log_level_with_token(
+        &self,
+        token: &str,
+        body: &serde_json::Value,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .put(&format!(
+                "http://{}:{}/admin/log-level",
+                &self.configuration.application.host, &self.configuration.application.port
+            ))
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Scrapes `/metrics` from the separate metrics port when one is
+    /// configured, otherwise from the application port.
+    pub async fn get_metrics(&self) -> String {
+        let port = self
+            .configuration
+            .metrics
+            .port
+            .unwrap_or(self.configuration.application.port);
+        reqwest::Client::new()
+            .get(&format!(
+                "http://{}:{}/metrics",
+                &self.configuration.application.host, port
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
 }
 
 pub fn assert_is_redirect_to(response: &reqwest::Response, location: &str) {
@@ -126,8 +568,13 @@ pub fn assert_is_redirect_to(response: &reqwest::Response, location: &str) {
     assert_eq!(response.headers().get("Location").unwrap(), location);
 }
 
-#[allow(clippy::let_underscore_future)]
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| {}).await
+}
+
+/// Like [`spawn_app`], with `customise` applied to the test configuration.
+#[allow(clippy::let_underscore_future)]
+pub async fn spawn_app_with(customise: impl FnOnce(&mut Settings)) -> TestApp {
     Lazy::force(&TRACING);
 
     let client = reqwest::Client::builder()
@@ -143,6 +590,8 @@ pub async fn spawn_app() -> TestApp {
         c.database.database_name = Uuid::new_v4().to_string();
         c.application.port = 0;
         c.email_client.base_url = email_server.uri();
+        c.metrics.port = c.metrics.port.map(|_| 0);
+        customise(&mut c);
         c
     };
 
@@ -160,6 +609,7 @@ pub async fn spawn_app() -> TestApp {
         .expect("Failed to build application.");
 
     configuration.application.port = application.port();
+    configuration.metrics.port = application.metrics_port();
 
     let _ = tokio::spawn(application.run_until_stopped());
 
@@ -181,6 +631,7 @@ pub struct TestUser {
     pub user_id: Thing,
     pub username: String,
     pub password: String,
+    pub role: String,
 }
 
 impl TestUser {
@@ -189,25 +640,34 @@ impl TestUser {
             user_id: Thing::from(("users".to_string(), Uuid::new_v4().to_string())),
             username: Uuid::new_v4().to_string(),
             password: Uuid::new_v4().to_string(),
+            role: "owner".into(),
         }
     }
 
-    #[tracing::instrument(name = "Store test user in database", skip(conn))]
-    async fn store(&self, conn: &Surreal<Client>) {
+    pub fn with_role(role: &str) -> Self {
+        Self {
+            role: role.into(),
+            ..Self::generate()
+        }
+    }
+
+    pub async fn store(&self, conn: &Surreal<Client>) {
+        self.store_with_params(conn, Params::new(15000, 2, 1, None).unwrap())
+            .await
+    }
+
+    #[tracing::instrument(name = "Store test user in database", skip(conn, params))]
+    pub async fn store_with_params(&self, conn: &Surreal<Client>, params: Params) {
         let salt = SaltString::generate(&mut rand::thread_rng());
 
-        let password_hash = Argon2::new(
-            Algorithm::Argon2id,
-            Version::V0x13,
-            Params::new(15000, 2, 1, None).unwrap(),
-        )
-        .hash_password(self.password.as_bytes(), &salt)
-        .unwrap()
-        .to_string();
+        let password_hash = Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password(self.password.as_bytes(), &salt)
+            .unwrap()
+            .to_string();
 
         let sql = format!(
-            "INSERT INTO users (id, username, password_hash) VALUES ({}, '{}', '{}')",
-            self.user_id, self.username, password_hash,
+            "INSERT INTO users (id, username, password_hash, role) VALUES ({}, '{}', '{}', '{}')",
+            self.user_id, self.username, password_hash, self.role,
         );
 
         conn.query(sql)
@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestUser};
+
+// The filter is process-wide, so only this test changes it; the directive
+// keeps `info` so spans other tests look for are still recorded.
+#[tokio::test]
+async fn owners_can_change_the_log_filter_until_the_ttl_expires() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let before: serde_json::Value = app.get_admin_log_level().await.json().await.unwrap();
+
+    // Act
+    let response = app
+        .put_admin_log_level(&serde_json::json!({
+            "directive": "info,zero2axum=debug",
+            "ttl_seconds": 1,
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let status: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(status["directive"], "info,zero2axum=debug");
+    assert_eq!(status["default_directive"], before["default_directive"]);
+    assert!(status["reverts_in_seconds"].as_u64().unwrap() <= 1);
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let status: serde_json::Value = app.get_admin_log_level().await.json().await.unwrap();
+    assert_eq!(status["directive"], before["default_directive"]);
+    assert!(status["reverts_in_seconds"].is_null());
+
+    let audit: serde_json::Value = app
+        .get_admin_audit(&[("action", "logging.filter_change")])
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(audit["entries"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn invalid_directives_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app
+        .put_admin_log_level(&serde_json::json!({ "directive": "zero2axum=loud" }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn overlong_ttls_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app
+        .put_admin_log_level(&serde_json::json!({
+            "directive": "info,zero2axum=debug",
+            "ttl_seconds": u64::MAX,
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_change_the_log_filter() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .put_admin_log_level(&serde_json::json!({ "directive": "debug" }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn only_owners_can_change_the_log_filter() {
+    // Arrange
+    let app = spawn_app().await;
+    let editor = TestUser::with_role("editor");
+    editor.store(&app.database.client).await;
+    app.post_login(&serde_json::json!({
+        "username": &editor.username,
+        "password": &editor.password
+    }))
+    .await;
+
+    // Act
+    let response = app
+        .put_admin_log_level(&serde_json::json!({ "directive": "debug" }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn tokens_need_the_logging_scope() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let response = app
+        .post_admin_tokens(&serde_json::json!({ "name": "ci", "scopes": ["publish"] }))
+        .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    let token = body["token"].as_str().unwrap();
+
+    // Act
+    let response = app
+        .put_admin_log_level_with_token(token, &serde_json::json!({ "directive": "debug" }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
+}
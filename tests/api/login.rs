@@ -1,4 +1,5 @@
-use crate::helpers::{assert_is_redirect_to, spawn_app};
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestUser};
+use argon2::Params;
 
 #[tokio::test]
 async fn an_error_flash_message_is_set_on_failure() {
@@ -24,3 +25,170 @@ async fn an_error_flash_message_is_set_on_failure() {
     let html_page = app.get_login_html().await;
     assert!(!html_page.contains(r#"Authentication failed"#));
 }
+
+#[tokio::test]
+async fn outdated_password_hashes_are_upgraded_on_login() {
+    // Arrange
+    let app = spawn_app().await;
+    let user = TestUser::generate();
+    user.store_with_params(&app.database.client, Params::new(4096, 1, 1, None).unwrap())
+        .await;
+
+    // Act
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": user.username,
+            "password": user.password
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/dashboard");
+
+    let mut res = app
+        .database
+        .client
+        .query("SELECT password_hash FROM users WHERE username = $username")
+        .bind(("username", &user.username))
+        .await
+        .unwrap();
+    let password_hash: Option<String> = res.take((0, "password_hash")).unwrap();
+    let password_hash = password_hash.expect("Test user not found.");
+
+    let argon2 = &app.configuration.authentication.argon2;
+    assert!(password_hash.starts_with("$argon2id$v=19$"));
+    assert!(password_hash.contains(&format!(
+        "m={},t={},p={}",
+        argon2.memory_size_kib, argon2.iterations, argon2.parallelism
+    )));
+}
+
+#[tokio::test]
+async fn a_successful_login_grants_access_to_admin_routes() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    app.login_as_test_user().await;
+
+    // Assert
+    let response = app.get_admin_users().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn logging_out_clears_the_session() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app.post_logout().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    let response = app.get_admin_users().await;
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn logging_in_again_invalidates_the_previous_session_id() {
+    // Arrange
+    let app = spawn_app().await;
+    let login_body = serde_json::json!({
+        "username": &app.test_user.username,
+        "password": &app.test_user.password
+    });
+    let response = app.post_login(&login_body).await;
+    let old_session_cookie = session_cookie(&response);
+
+    // Act
+    let response = app.post_login(&login_body).await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+
+    // Assert
+    let response = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap()
+        .get(&format!(
+            "http://{}:{}/admin/users",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .header("Cookie", old_session_cookie)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn a_locked_out_user_cannot_log_in_even_with_the_right_password() {
+    // Arrange
+    let app = spawn_app().await;
+    let max_failures = app
+        .configuration
+        .authentication
+        .throttle
+        .max_failures_per_username;
+    for _ in 0..max_failures {
+        let response = app
+            .post_login(&serde_json::json!({
+                "username": &app.test_user.username,
+                "password": "wrong-password"
+            }))
+            .await;
+        assert_is_redirect_to(&response, "/login");
+    }
+
+    // Act
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    let html_page = app.get_login_html().await;
+    assert!(html_page.contains(r#"<p class="error"><i>Authentication failed.</i></p>"#));
+}
+
+#[tokio::test]
+async fn a_lockout_looks_the_same_for_unknown_users() {
+    // Arrange
+    let app = spawn_app().await;
+    let max_failures = app
+        .configuration
+        .authentication
+        .throttle
+        .max_failures_per_username;
+    let login_body = serde_json::json!({
+        "username": "random-username",
+        "password": "random-password"
+    });
+    for _ in 0..max_failures {
+        app.post_login(&login_body).await;
+    }
+
+    // Act
+    let response = app.post_login(&login_body).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    let html_page = app.get_login_html().await;
+    assert!(html_page.contains(r#"<p class="error"><i>Authentication failed.</i></p>"#));
+}
+
+fn session_cookie(response: &reqwest::Response) -> String {
+    response
+        .headers()
+        .get_all("Set-Cookie")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .find(|c| c.starts_with("session_id="))
+        .and_then(|c| c.split(';').next())
+        .expect("No session cookie was set.")
+        .to_string()
+}
@@ -1,6 +1,21 @@
+mod admin_dashboard;
+mod admin_users;
+mod api_tokens;
+mod audit_log;
+mod change_password;
+mod csrf;
 mod health_check;
 mod helpers;
+mod log_level;
 mod login;
+mod metrics;
 mod newsletter;
+mod openapi;
+mod problems;
+mod request_id;
+mod roles;
+mod security_headers;
 mod subscriptions;
 mod subscriptions_confirm;
+mod telemetry;
+mod two_factor;
@@ -0,0 +1,154 @@
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::{spawn_app, spawn_app_with};
+
+/// Whether any sample of `metric` carries every one of `labels`.
+fn has_sample(metrics: &str, metric: &str, labels: &[&str]) -> bool {
+    metrics.lines().any(|line| {
+        line.starts_with(&format!("{}{{", metric))
+            && labels.iter().all(|label| line.contains(label))
+    })
+}
+
+#[tokio::test]
+async fn http_requests_are_counted_and_timed_by_route_and_status() {
+    // Arrange
+    let app = spawn_app().await;
+    let client = reqwest::Client::new();
+    let base = format!(
+        "http://{}:{}",
+        &app.configuration.application.host, &app.configuration.application.port
+    );
+    client
+        .get(&format!("{}/health_check", base))
+        .send()
+        .await
+        .unwrap();
+    client
+        .get(&format!("{}/no/such/page", base))
+        .send()
+        .await
+        .unwrap();
+
+    // Act
+    let metrics = app.get_metrics().await;
+
+    // Assert
+    let health_check = [
+        r#"route="/health_check""#,
+        r#"method="GET""#,
+        r#"status="200""#,
+    ];
+    assert!(has_sample(&metrics, "http_requests_total", &health_check));
+    assert!(has_sample(
+        &metrics,
+        "http_request_duration_seconds_bucket",
+        &health_check
+    ));
+    assert!(has_sample(
+        &metrics,
+        "http_requests_total",
+        &[r#"route="unmatched""#, r#"status="404""#]
+    ));
+    assert!(!metrics.contains("/no/such/page"));
+}
+
+#[tokio::test]
+async fn sign_ups_emails_and_queries_are_recorded() {
+    // Arrange
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com".into())
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let metrics = app.get_metrics().await;
+
+    // Assert
+    assert!(metrics.contains("subscriber_signups_total "));
+    assert!(has_sample(
+        &metrics,
+        "emails_sent_total",
+        &[r#"transport="postmark""#]
+    ));
+    assert!(has_sample(
+        &metrics,
+        "surrealdb_query_duration_seconds_bucket",
+        &[r#"operation="subscriber.insert""#]
+    ));
+}
+
+#[tokio::test]
+async fn failed_emails_are_counted() {
+    // Arrange
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com".into())
+        .await;
+    let metrics = app.get_metrics().await;
+
+    // Assert
+    assert!(has_sample(
+        &metrics,
+        "emails_failed_total",
+        &[r#"transport="postmark""#]
+    ));
+}
+
+#[tokio::test]
+async fn metrics_are_not_public_by_default() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .get(&format!(
+            "http://{}:{}/metrics",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert!(app.configuration.metrics.port.is_some());
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn metrics_can_be_moved_to_a_separate_port() {
+    // Arrange
+    let app = spawn_app_with(|c| c.metrics.port = Some(0)).await;
+
+    // Act
+    let public = reqwest::Client::new()
+        .get(&format!(
+            "http://{}:{}/metrics",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let metrics = app.get_metrics().await;
+
+    // Assert
+    assert_ne!(
+        app.configuration.metrics.port,
+        Some(app.configuration.application.port)
+    );
+    assert_eq!(public.status().as_u16(), 404);
+    assert!(metrics.contains("http_requests_total"));
+}
@@ -0,0 +1,48 @@
+use utoipa::OpenApi;
+use zero2axum::openapi::ApiDoc;
+
+use crate::helpers::spawn_app;
+
+const COMMITTED_SPEC: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+// Run with `UPDATE_OPENAPI=1` to rewrite the committed spec after an
+// intentional API change.
+#[test]
+fn the_committed_spec_matches_the_code() {
+    let generated = ApiDoc::openapi()
+        .to_pretty_json()
+        .expect("Failed to render the OpenAPI document.");
+    if std::env::var("UPDATE_OPENAPI").is_ok() {
+        std::fs::write(COMMITTED_SPEC, format!("{}\n", generated)).unwrap();
+        return;
+    }
+
+    let committed: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(COMMITTED_SPEC).unwrap()).unwrap();
+    let generated: serde_json::Value = serde_json::from_str(&generated).unwrap();
+    assert_eq!(
+        committed, generated,
+        "openapi.json is out of date; regenerate it with `UPDATE_OPENAPI=1 cargo test openapi`."
+    );
+}
+
+#[tokio::test]
+async fn the_spec_is_served_at_openapi_json() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::get(&format!(
+        "http://{}:{}/openapi.json",
+        app.configuration.application.host, app.configuration.application.port
+    ))
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let spec: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(spec["openapi"], "3.1.0");
+    assert!(spec["paths"]["/subscribe"]["post"].is_object());
+    assert!(spec["components"]["schemas"]["Problem"].is_object());
+}
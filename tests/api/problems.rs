@@ -0,0 +1,98 @@
+use crate::helpers::{spawn_app, TestApp};
+
+async fn confirm_with_unknown_token(app: &TestApp, accept: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(&format!(
+            "http://{}:{}/subscribe/confirm?subscription_token=unknown",
+            app.configuration.application.host, app.configuration.application.port
+        ))
+        .header("Accept", accept)
+        .header("X-Request-Id", "support-ticket-1234")
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn errors_are_problem_details_with_a_stable_code() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = confirm_with_unknown_token(&app, "application/json").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(
+        response.headers()["Content-Type"],
+        "application/problem+json"
+    );
+    let problem: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(problem["status"], 401);
+    assert_eq!(problem["code"], "unknown_subscription_token");
+    assert_eq!(problem["type"], "/problems/unknown_subscription_token");
+    assert_eq!(problem["request_id"], "support-ticket-1234");
+}
+
+#[tokio::test]
+async fn browsers_get_an_error_page() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = confirm_with_unknown_token(&app, "text/html,*/*;q=0.8").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
+    assert!(response.headers()["Content-Type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+    let html = response.text().await.unwrap();
+    assert!(html.contains("Unknown subscription token"));
+    assert!(html.contains("support-ticket-1234"));
+}
+
+#[tokio::test]
+async fn extractor_rejections_are_problem_details_too() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::get(&format!(
+        "http://{}:{}/subscribe/confirm",
+        app.configuration.application.host, app.configuration.application.port
+    ))
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    let problem: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(problem["code"], "bad_request");
+    assert!(problem["request_id"].is_string());
+}
+
+#[tokio::test]
+async fn unexpected_errors_do_not_leak_their_cause() {
+    // Arrange
+    let app = spawn_app().await;
+    let sql = "DEFINE FIELD subscription_token ON subscription_tokens TYPE number ASSERT $value != NONE AND is::numeric($value);";
+    app.database
+        .client
+        .query(sql)
+        .await
+        .expect("Failed to update a table.");
+
+    // Act
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com".into())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 500);
+    let problem: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(problem["code"], "internal_error");
+    assert!(problem.get("detail").is_none());
+    assert!(!problem.to_string().contains("subscription_token"));
+}
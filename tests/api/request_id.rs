@@ -0,0 +1,99 @@
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn a_request_id_is_generated_and_echoed() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .get(&format!(
+            "http://{}:{}/health_check",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    let request_id = response.headers()["X-Request-Id"].to_str().unwrap();
+    assert!(uuid::Uuid::parse_str(request_id).is_ok());
+}
+
+#[tokio::test]
+async fn an_incoming_request_id_is_honoured() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .get(&format!(
+            "http://{}:{}/health_check",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .header("X-Request-Id", "support-ticket-1234")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.headers()["X-Request-Id"], "support-ticket-1234");
+}
+
+#[tokio::test]
+async fn error_bodies_include_the_request_id() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .get(&format!(
+            "http://{}:{}/subscribers",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .header("X-Request-Id", "support-ticket-1234")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["request_id"], "support-ticket-1234");
+}
+
+#[tokio::test]
+async fn the_request_id_is_forwarded_to_the_email_provider() {
+    // Arrange
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .and(header("X-Request-Id", "support-ticket-1234"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(&format!(
+            "http://{}:{}/subscribe",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .header("X-Request-Id", "support-ticket-1234")
+        .header("X-CSRF-Token", app.csrf_token().await)
+        .form(&serde_json::json!({
+            "name": "le guin",
+            "email": "ursula_le_guin@gmail.com"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+}
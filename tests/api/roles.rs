@@ -0,0 +1,126 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp, TestUser};
+
+async fn log_in_with_role(app: &TestApp, role: &str) -> TestUser {
+    let user = TestUser::with_role(role);
+    user.store(&app.database.client).await;
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &user.username,
+            "password": &user.password
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+    user
+}
+
+fn publish_form() -> serde_json::Value {
+    serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+    })
+}
+
+// region: -- Viewers
+#[tokio::test]
+async fn viewers_can_see_the_dashboard() {
+    // Arrange
+    let app = spawn_app().await;
+    log_in_with_role(&app, "viewer").await;
+
+    // Act
+    let response = app.get_admin_dashboard().await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn viewers_cannot_publish() {
+    // Arrange
+    let app = spawn_app().await;
+    let viewer = log_in_with_role(&app, "viewer").await;
+
+    // Act 1 - Through the form
+    let response = app.post_admin_newsletters(&publish_form()).await;
+    assert_eq!(response.status().as_u16(), 403);
+
+    // Act 2 - Through the API
+    let response = reqwest::Client::new()
+        .post(&format!(
+            "http://{}:{}/newsletters",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .basic_auth(&viewer.username, Some(&viewer.password))
+        .json(&serde_json::json!({
+            "title": "Newsletter title",
+            "content": {
+                "text": "Newsletter body as plain text",
+                "html": "<p>Newsletter body as HTML</p>",
+            }
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+}
+// endregion: -- Viewers
+
+// region: -- Editors
+#[tokio::test]
+async fn editors_can_publish_but_not_manage_users() {
+    // Arrange
+    let app = spawn_app().await;
+    log_in_with_role(&app, "editor").await;
+
+    // Act
+    let publish = app.post_admin_newsletters(&publish_form()).await;
+    let list_users = app.get_admin_users().await;
+
+    // Assert
+    assert_is_redirect_to(&publish, "/admin/issues");
+    assert_eq!(list_users.status().as_u16(), 403);
+}
+// endregion: -- Editors
+
+// region: -- Owners
+#[tokio::test]
+async fn owners_can_change_roles() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    let editor = TestUser::with_role("editor");
+    editor.store(&app.database.client).await;
+
+    // Act
+    let response = app.put_admin_user_role(&editor.username, "viewer").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let users: serde_json::Value = app.get_admin_users().await.json().await.unwrap();
+    let user = users
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|u| u["username"] == editor.username.as_str())
+        .unwrap();
+    assert_eq!(user["role"], "viewer");
+}
+
+#[tokio::test]
+async fn the_last_owner_cannot_be_demoted() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app
+        .put_admin_user_role(&app.test_user.username, "editor")
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+// endregion: -- Owners
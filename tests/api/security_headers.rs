@@ -0,0 +1,110 @@
+use crate::helpers::spawn_app;
+
+fn style_nonce(html: &str) -> &str {
+    let (_, rest) = html
+        .split_once(r#"<style nonce=""#)
+        .expect("The inline style has no nonce.");
+    rest.split('"').next().unwrap()
+}
+
+#[tokio::test]
+async fn html_pages_carry_the_security_headers() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(&format!(
+            "http://{}:{}/login",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    let headers = response.headers().clone();
+    assert_eq!(headers["X-Frame-Options"], "DENY");
+    assert_eq!(
+        headers["Referrer-Policy"],
+        "strict-origin-when-cross-origin"
+    );
+    assert_eq!(
+        headers["Strict-Transport-Security"],
+        "max-age=31536000; includeSubDomains"
+    );
+    assert!(headers["Permissions-Policy"]
+        .to_str()
+        .unwrap()
+        .contains("camera=()"));
+
+    let csp = headers["Content-Security-Policy"].to_str().unwrap();
+    assert!(csp.contains("default-src 'self'"));
+    assert!(csp.contains("frame-ancestors 'none'"));
+    assert!(!csp.contains("data:"));
+
+    let html = response.text().await.unwrap();
+    assert!(csp.contains(&format!("'nonce-{}'", style_nonce(&html))));
+}
+
+#[tokio::test]
+async fn every_request_gets_a_fresh_nonce() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let first = app.get_login_html().await;
+    let second = app.get_login_html().await;
+
+    // Assert
+    assert_ne!(style_nonce(&first), style_nonce(&second));
+}
+
+#[tokio::test]
+async fn api_responses_carry_the_security_headers() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .get(&format!(
+            "http://{}:{}/health_check",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.headers()["X-Frame-Options"], "DENY");
+    assert!(response.headers().contains_key("Content-Security-Policy"));
+}
+
+#[tokio::test]
+async fn the_two_factor_page_may_show_inline_qr_codes() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(&format!(
+            "http://{}:{}/admin/two-factor",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let csp = response.headers()["Content-Security-Policy"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(csp.contains("img-src 'self' data:"));
+    let html = response.text().await.unwrap();
+    assert!(csp.contains(&format!("'nonce-{}'", style_nonce(&html))));
+}
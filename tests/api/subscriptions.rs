@@ -1,4 +1,4 @@
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, TestApp};
 use rstest::rstest;
 use surrealdb::sql::Thing;
 use wiremock::matchers::{method, path};
@@ -110,19 +110,9 @@ async fn subscribe_returns_a_200_when_fields_are_present_but_empty(
 ) {
     // Arrange
     let app = spawn_app().await;
-    let client = reqwest::Client::new();
 
     // Act
-    let response = client
-        .post(&format!(
-            "http://{}:{}/subscribe",
-            &app.configuration.application.host, &app.configuration.application.port
-        ))
-        .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(invalid_body.to_string())
-        .send()
-        .await
-        .expect("Failed to execute request.");
+    let response = app.post_subscriptions(invalid_body.to_string()).await;
 
     // Assert
     assert_eq!(
@@ -134,6 +124,49 @@ async fn subscribe_returns_a_200_when_fields_are_present_but_empty(
 }
 // endregion: -- POST Form: 200 w/fields present but empty
 
+// region: -- POST Form: every invalid field is reported
+#[tokio::test]
+async fn subscribe_reports_every_invalid_field() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_subscriptions("name=Ursula%20%3Cb%3E&email=definitely-not-an-email".into())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    let problem: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(problem["code"], "validation_failed");
+    assert_eq!(
+        problem["errors"],
+        serde_json::json!([
+            { "field": "name", "code": "forbidden_character", "message": "The name must not contain `<`." },
+            { "field": "email", "code": "invalid_syntax", "message": "This does not look like an email address." },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn browsers_get_the_form_back_with_inline_errors() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_subscriptions_html("name=&email=ursula.example.com".into())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    let html = response.text().await.unwrap();
+    assert!(html.contains("Please enter your name."));
+    assert!(html.contains("This does not look like an email address."));
+    assert!(html.contains(r#"value="ursula.example.com""#));
+}
+// endregion: -- POST Form: every invalid field is reported
+
 // region: -- POST Form: Subscribe sends a confirmation email for valid data
 #[tokio::test]
 async fn subscribe_sends_a_confirmation_email_with_a_link() {
@@ -159,6 +192,98 @@ async fn subscribe_sends_a_confirmation_email_with_a_link() {
 }
 // endregion: -- POST Form: Subscribe sends a confirmation email for valid data
 
+// region: -- POST JSON: the status tells JSON clients what happened
+fn json_body() -> serde_json::Value {
+    serde_json::json!({ "name": "le guin", "email": "ursula_le_guin@gmail.com" })
+}
+
+async fn confirm(app: &TestApp, email_request: &wiremock::Request) {
+    let confirmation_links = app.get_confirmation_links(email_request);
+    reqwest::get(confirmation_links.html)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn json_clients_learn_whether_the_address_is_new_pending_or_confirmed() {
+    // Arrange
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+
+    // Act & Assert - a new address
+    let response = app.post_subscriptions_json(&json_body()).await;
+    assert_eq!(response.status().as_u16(), 201);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({ "status": "pending_confirmation", "confirmation_email_sent": true })
+    );
+
+    // Act & Assert - still pending, the confirmation email is sent again
+    let response = app.post_subscriptions_json(&json_body()).await;
+    assert_eq!(response.status().as_u16(), 202);
+
+    // Act & Assert - confirmed, nothing is sent
+    let email_request = &app.email_server.received_requests().await.unwrap()[1];
+    confirm(&app, email_request).await;
+    let response = app.post_subscriptions_json(&json_body()).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({ "status": "confirmed", "confirmation_email_sent": false })
+    );
+}
+
+#[tokio::test]
+async fn form_clients_cannot_tell_whether_an_address_is_subscribed() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let new = app.post_subscriptions(body.into()).await;
+    let pending = app.post_subscriptions(body.into()).await;
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    confirm(&app, email_request).await;
+    let confirmed = app.post_subscriptions(body.into()).await;
+
+    // Assert
+    for response in [new, pending, confirmed] {
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.text().await.unwrap(), "");
+    }
+}
+
+#[tokio::test]
+async fn invalid_json_subscriptions_are_rejected_with_field_errors() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_subscriptions_json(&serde_json::json!({ "name": "", "email": "not-an-email" }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    let problem: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(problem["errors"].as_array().unwrap().len(), 2);
+}
+// endregion: -- POST JSON: the status tells JSON clients what happened
+
 // region: -- Subscribe Fails if there is a Fatal Database Error
 #[tokio::test]
 async fn subscribe_fails_if_there_is_a_fatal_database_error() {
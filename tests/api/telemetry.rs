@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use opentelemetry::{sdk::export::trace::SpanData, Key};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::{spawn_app, TestApp, TEST_EXPORTER};
+
+const PARENT_SPAN_ID: &str = "00f067aa0ba902b7";
+
+/// Subscribes as a caller in trace `trace_id`.
+async fn subscribe_in_trace(app: &TestApp, trace_id: &str) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app
+        .api_client
+        .post(&format!(
+            "http://{}:{}/subscribe",
+            &app.configuration.application.host, &app.configuration.application.port
+        ))
+        .header(
+            "traceparent",
+            format!("00-{}-{}-01", trace_id, PARENT_SPAN_ID),
+        )
+        .header("X-CSRF-Token", app.csrf_token().await)
+        .form(&serde_json::json!({
+            "name": "le guin",
+            "email": "ursula_le_guin@gmail.com"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+/// Waits for the span called `name` in trace `trace_id` to be exported.
+async fn exported_span(trace_id: &str, name: &str) -> SpanData {
+    for _ in 0..50 {
+        let span = TEST_EXPORTER
+            .spans()
+            .into_iter()
+            .find(|span| span.name == name && span.span_context.trace_id().to_string() == trace_id);
+        if let Some(span) = span {
+            return span;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("The {} span was not exported.", name);
+}
+
+fn attribute(span: &SpanData, key: &'static str) -> Option<String> {
+    span.attributes
+        .get(&Key::from_static_str(key))
+        .map(|value| value.to_string())
+}
+
+#[tokio::test]
+async fn an_incoming_traceparent_is_continued_and_forwarded_to_the_email_provider() {
+    // Arrange
+    let app = spawn_app().await;
+    let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    // Act
+    subscribe_in_trace(&app, trace_id).await;
+
+    // Assert
+    // The request span is exported once the response body has been sent
+    let request_span = exported_span(trace_id, "request").await;
+    assert_eq!(request_span.parent_span_id.to_string(), PARENT_SPAN_ID);
+
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let traceparent = email_request
+        .headers
+        .get(&"traceparent".into())
+        .expect("No traceparent was sent to the email provider.")
+        .last()
+        .as_str();
+    assert!(traceparent.starts_with(&format!("00-{}-", trace_id)));
+    assert!(!traceparent.contains(PARENT_SPAN_ID));
+}
+
+#[tokio::test]
+async fn exported_spans_do_not_leak_subscriber_details() {
+    // Arrange
+    let app = spawn_app().await;
+    let trace_id = "5c0a3f4688c45eb7b4df030e1f1f5847";
+
+    // Act
+    subscribe_in_trace(&app, trace_id).await;
+
+    // Assert
+    let subscribe_span = exported_span(trace_id, "Adding a new subscriber.").await;
+    let email = attribute(&subscribe_span, "subscriber_email").unwrap();
+    assert!(email.starts_with("email:"));
+    assert_eq!(
+        attribute(&subscribe_span, "subscriber_name").unwrap(),
+        "[REDACTED]"
+    );
+
+    let email_span = exported_span(trace_id, "Sending email").await;
+    assert_eq!(attribute(&email_span, "recipient").unwrap(), email);
+    assert!(attribute(&email_span, "html_content").is_none());
+    assert!(attribute(&email_span, "text_content").is_none());
+}
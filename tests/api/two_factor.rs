@@ -0,0 +1,192 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+use secrecy::Secret;
+use zero2axum::authentication::{get_totp_secret, totp_for};
+
+/// Logs in, enrolls the test user and returns the recovery codes.
+async fn enroll_test_user(app: &TestApp) -> Vec<String> {
+    app.login_as_test_user().await;
+    let html = app.get_two_factor_html().await;
+    assert!(html.contains("Scan this QR code"));
+
+    let mut res = app
+        .database
+        .client
+        .query("SELECT totp_pending_secret FROM $user_id")
+        .bind(("user_id", &app.test_user.user_id))
+        .await
+        .unwrap();
+    let secret: Option<String> = res.take((0, "totp_pending_secret")).unwrap();
+    let code = current_code(app, Secret::new(secret.unwrap()));
+
+    let response = app.post_two_factor(&code).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let html = response.text().await.unwrap();
+    assert!(html.contains("Two-factor authentication is now enabled."));
+
+    html.split("<code>")
+        .skip(1)
+        .filter_map(|s| s.split("</code>").next())
+        .map(str::to_string)
+        .collect()
+}
+
+fn current_code(app: &TestApp, secret: Secret<String>) -> String {
+    totp_for(&secret, &app.test_user.username)
+        .unwrap()
+        .generate_current()
+        .unwrap()
+}
+
+async fn log_in_with_password(app: &TestApp) {
+    app.post_logout().await;
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/login/two-factor");
+}
+
+// region: -- Enrollment
+#[tokio::test]
+async fn enrolling_yields_recovery_codes() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let recovery_codes = enroll_test_user(&app).await;
+
+    // Assert
+    assert_eq!(recovery_codes.len(), 10);
+    let secret = get_totp_secret(&app.test_user.user_id, &app.database.client)
+        .await
+        .unwrap();
+    assert!(secret.is_some());
+}
+
+#[tokio::test]
+async fn a_wrong_enrollment_code_does_not_enable_two_factor() {
+    // Arrange
+    let app = spawn_app().await;
+    app.login_as_test_user().await;
+    app.get_two_factor_html().await;
+
+    // Act
+    let response = app.post_two_factor("000000x").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/two-factor");
+    let secret = get_totp_secret(&app.test_user.user_id, &app.database.client)
+        .await
+        .unwrap();
+    assert!(secret.is_none());
+}
+// endregion: -- Enrollment
+
+// region: -- Second login step
+#[tokio::test]
+async fn a_password_alone_does_not_log_in_an_enrolled_user() {
+    // Arrange
+    let app = spawn_app().await;
+    enroll_test_user(&app).await;
+
+    // Act
+    log_in_with_password(&app).await;
+
+    // Assert
+    let response = app.get_admin_dashboard().await;
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn a_valid_totp_code_completes_the_login() {
+    // Arrange
+    let app = spawn_app().await;
+    enroll_test_user(&app).await;
+    log_in_with_password(&app).await;
+    let secret = get_totp_secret(&app.test_user.user_id, &app.database.client)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Act
+    let response = app.post_login_two_factor(&current_code(&app, secret)).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/dashboard");
+    let response = app.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn a_totp_code_can_only_be_used_once() {
+    // Arrange
+    let app = spawn_app().await;
+    enroll_test_user(&app).await;
+    let secret = get_totp_secret(&app.test_user.user_id, &app.database.client)
+        .await
+        .unwrap()
+        .unwrap();
+    let code = current_code(&app, secret);
+
+    // Act 1 - Use the code
+    log_in_with_password(&app).await;
+    let response = app.post_login_two_factor(&code).await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+
+    // Act 2 - Replay it
+    app.post_logout().await;
+    log_in_with_password(&app).await;
+    let response = app.post_login_two_factor(&code).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login/two-factor");
+}
+
+#[tokio::test]
+async fn recovery_codes_can_only_be_used_once() {
+    // Arrange
+    let app = spawn_app().await;
+    let recovery_codes = enroll_test_user(&app).await;
+
+    // Act 1 - Use a recovery code
+    log_in_with_password(&app).await;
+    let response = app.post_login_two_factor(&recovery_codes[0]).await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+
+    // Act 2 - Use it again
+    log_in_with_password(&app).await;
+    let response = app.post_login_two_factor(&recovery_codes[0]).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login/two-factor");
+}
+// endregion: -- Second login step
+
+// region: -- API access
+#[tokio::test]
+async fn an_enrolled_user_cannot_publish_with_basic_auth() {
+    // Arrange
+    let app = spawn_app().await;
+    enroll_test_user(&app).await;
+
+    // Act
+    let response = app
+        .post_newsletters(serde_json::json!({
+            "title": "Newsletter title",
+            "content": {
+                "text": "Newsletter content as plain text",
+                "html": "<p>Newsletter content as HTML</p>"
+            }
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(
+        response.headers()["WWW-Authenticate"],
+        r#"Bearer realm="publish""#
+    );
+}
+// endregion: -- API access